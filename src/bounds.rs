@@ -0,0 +1,116 @@
+use crate::na::{Matrix4, Point3, Vector3};
+
+/// An axis aligned bounding box
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Aabb {
+    pub min: Vector3<f32>,
+    pub max: Vector3<f32>,
+}
+
+impl Aabb {
+    pub fn new(min: Vector3<f32>, max: Vector3<f32>) -> Self {
+        Self { min, max }
+    }
+
+    /// A zero sized box at the origin if there are no points
+    pub fn from_points(points: &[Vector3<f32>]) -> Self {
+        match points.split_first() {
+            Some((first, rest)) => rest.iter().fold(Self::new(*first, *first), |aabb, point| {
+                Self::new(aabb.min.inf(point), aabb.max.sup(point))
+            }),
+            None => Self::default(),
+        }
+    }
+
+    pub fn get_center(&self) -> Vector3<f32> {
+        (self.min + self.max) / 2.0
+    }
+
+    pub fn get_size(&self) -> Vector3<f32> {
+        self.max - self.min
+    }
+
+    /// Half the size
+    pub fn get_extents(&self) -> Vector3<f32> {
+        self.get_size() / 2.0
+    }
+
+    pub fn contains_point(&self, point: &Vector3<f32>) -> bool {
+        point >= &self.min && point <= &self.max
+    }
+
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        self.min <= other.max && self.max >= other.min
+    }
+
+    /// The smallest box containing both boxes
+    pub fn merge(&self, other: &Aabb) -> Self {
+        Self::new(self.min.inf(&other.min), self.max.sup(&other.max))
+    }
+
+    /// The box containing the transformed box, which is larger if it's rotated
+    pub fn transform(&self, matrix: &Matrix4<f32>) -> Self {
+        let center = matrix
+            .transform_point(&Point3::from(self.get_center()))
+            .coords;
+        let extents = matrix.fixed_view::<3, 3>(0, 0).abs() * self.get_extents();
+
+        Self::new(center - extents, center + extents)
+    }
+}
+
+impl Default for Aabb {
+    fn default() -> Self {
+        Self::new(Vector3::zeros(), Vector3::zeros())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BoundingSphere {
+    pub center: Vector3<f32>,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    pub fn new(center: Vector3<f32>, radius: f32) -> Self {
+        Self { center, radius }
+    }
+
+    /// Centered on the bounding box of the points, which is close to the smallest sphere for
+    /// most meshes
+    pub fn from_points(points: &[Vector3<f32>]) -> Self {
+        let center = Aabb::from_points(points).get_center();
+        let radius = points
+            .iter()
+            .map(|point| (point - center).norm())
+            .fold(0.0, f32::max);
+
+        Self::new(center, radius)
+    }
+
+    pub fn contains_point(&self, point: &Vector3<f32>) -> bool {
+        (point - self.center).norm_squared() <= self.radius * self.radius
+    }
+
+    pub fn intersects(&self, other: &BoundingSphere) -> bool {
+        (other.center - self.center).norm() <= self.radius + other.radius
+    }
+
+    /// Scales the radius by the largest axis scale so the sphere still contains the mesh
+    pub fn transform(&self, matrix: &Matrix4<f32>) -> Self {
+        let center = matrix.transform_point(&Point3::from(self.center)).coords;
+        let scale = (0..3)
+            .map(|i| matrix.fixed_view::<3, 1>(0, i).norm())
+            .fold(0.0, f32::max);
+
+        Self::new(center, self.radius * scale)
+    }
+}
+
+impl Default for BoundingSphere {
+    fn default() -> Self {
+        Self::new(Vector3::zeros(), 0.0)
+    }
+}
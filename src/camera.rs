@@ -1,19 +1,34 @@
 use crate::{
-    na::{Matrix4, Orthographic3, Perspective3, Vector2},
-    GameObject, Transform,
+    na::{Matrix4, Orthographic3, Perspective3, Vector2, Vector3, Vector4},
+    GameObject, Ray, Transform,
 };
 use palette::LinSrgba;
 
 #[derive(Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CameraType {
     Perspective(Perspective3<f32>),
     Orthographic(Orthographic3<f32>, OrthographicType),
+    /// Any projection matrix. It isn't touched when the screen size changes.
+    Custom(Matrix4<f32>),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum OrthographicType {
-    UI { height: f32 },
-    World { height: f32 },
+    UI {
+        height: f32,
+    },
+    World {
+        height: f32,
+    },
+    /// Renders `reference_resolution` pixels scaled by the largest integer that fits the screen,
+    /// letterboxing or pillarboxing the rest. `zoom` should stay a whole number (or its inverse) to keep pixels crisp.
+    PixelPerfect {
+        reference_resolution: Vector2<u32>,
+        pixels_per_unit: f32,
+        zoom: f32,
+    },
 }
 
 impl OrthographicType {
@@ -23,6 +38,88 @@ impl OrthographicType {
             _ => false,
         }
     }
+
+    pub fn is_pixel_perfect(&self) -> bool {
+        matches!(self, OrthographicType::PixelPerfect { .. })
+    }
+
+    /// Returns `(left, right, top, bottom)`
+    fn get_bounds(&self, screen_size: Vector2<u32>) -> (f32, f32, f32, f32) {
+        match *self {
+            OrthographicType::UI { height } => {
+                let width = height * ((screen_size.x as f32) / (screen_size.y as f32));
+                (0.0, width, 0.0, height)
+            }
+            OrthographicType::World { height } => {
+                let width = height * ((screen_size.x as f32) / (screen_size.y as f32));
+                (-width / 2.0, width / 2.0, height / 2.0, -height / 2.0)
+            }
+            OrthographicType::PixelPerfect {
+                reference_resolution,
+                pixels_per_unit,
+                zoom,
+            } => {
+                let width = (reference_resolution.x as f32) / (pixels_per_unit * zoom);
+                let height = (reference_resolution.y as f32) / (pixels_per_unit * zoom);
+                (-width / 2.0, width / 2.0, height / 2.0, -height / 2.0)
+            }
+        }
+    }
+
+    /// The integer scale applied to the reference resolution, 1 for non pixel perfect types
+    fn get_pixel_scale(&self, screen_size: Vector2<u32>) -> u32 {
+        match *self {
+            OrthographicType::PixelPerfect {
+                reference_resolution,
+                ..
+            } => (screen_size.x / reference_resolution.x.max(1))
+                .min(screen_size.y / reference_resolution.y.max(1))
+                .max(1),
+            _ => 1,
+        }
+    }
+
+    /// Returns the viewport `(position, size)` with the letterbox or pillarbox removed
+    fn get_viewport(&self, screen_size: Vector2<u32>) -> (Vector2<i32>, Vector2<u32>) {
+        match *self {
+            OrthographicType::PixelPerfect {
+                reference_resolution,
+                ..
+            } => {
+                let size = reference_resolution * self.get_pixel_scale(screen_size);
+                (
+                    Vector2::new(
+                        (screen_size.x as i32 - size.x as i32) / 2,
+                        (screen_size.y as i32 - size.y as i32) / 2,
+                    ),
+                    size,
+                )
+            }
+            _ => (Vector2::zeros(), screen_size),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DepthMode {
+    /// -1 to 1 clip space depth, cleared to 1 and compared with `LESS`
+    Standard,
+    /// Perspective only. 0 to 1 clip space depth with the near plane at 1 and an infinite far plane,
+    /// cleared to 0 and compared with `GREATER`. Render into a `DepthFormat::Float32` target for the full precision.
+    /// Custom cameras must use a matrix like `Camera::reverse_z_frustum_matrix`.
+    ReverseZ,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ClearFlags {
+    /// Clears color and depth
+    Color,
+    DepthOnly,
+    /// Clears color and depth, a `CameraStack` then draws the camera's skybox behind everything
+    Skybox,
+    Nothing,
 }
 
 #[derive(Clone, Copy)]
@@ -30,7 +127,47 @@ pub struct Camera {
     pub transform: Transform,
     pub camera_type: CameraType,
     pub clear_color: LinSrgba,
+    /// Only objects whose layer shares a bit with this mask are drawn.
+    pub culling_mask: u32,
+    pub depth_mode: DepthMode,
+    pub clear_flags: ClearFlags,
+    /// Cameras with a lower priority are rendered first in a `CameraStack`
+    pub priority: i32,
+    /// Offsets the projection by a sub-pixel Halton sequence each `next_frame` for temporal anti-aliasing
+    pub jitter_enabled: bool,
     screen_size: Vector2<u32>,
+    viewport_position: Vector2<i32>,
+    viewport_size: Vector2<u32>,
+    pixel_scale: u32,
+    framebuffer_size: Vector2<u32>,
+    fov_animation: Option<FovAnimation>,
+    frame_index: u32,
+    jitter: Vector2<f32>,
+    view_projection: Matrix4<f32>,
+    previous_view_projection: Matrix4<f32>,
+}
+
+const JITTER_SAMPLES: u32 = 8;
+
+fn halton(mut index: u32, base: u32) -> f32 {
+    let mut fraction = 1.0;
+    let mut result = 0.0;
+
+    while index > 0 {
+        fraction /= base as f32;
+        result += fraction * (index % base) as f32;
+        index /= base;
+    }
+
+    result
+}
+
+#[derive(Debug, Clone, Copy)]
+struct FovAnimation {
+    start_fov: f32,
+    target_fov: f32,
+    duration: f32,
+    elapsed: f32,
 }
 
 impl Camera {
@@ -50,10 +187,138 @@ impl Camera {
                 far_clipping_plane,
             )),
             clear_color,
+            culling_mask: u32::MAX,
+            depth_mode: DepthMode::Standard,
+            clear_flags: ClearFlags::Color,
+            priority: 0,
+            jitter_enabled: false,
+            screen_size,
+            viewport_position: Vector2::zeros(),
+            viewport_size: screen_size,
+            pixel_scale: 1,
+            framebuffer_size: screen_size,
+            fov_animation: None,
+            frame_index: 0,
+            jitter: Vector2::zeros(),
+            view_projection: Matrix4::identity(),
+            previous_view_projection: Matrix4::identity(),
+        }
+    }
+
+    pub fn new_custom(
+        projection_matrix: Matrix4<f32>,
+        screen_size: Vector2<u32>,
+        clear_color: LinSrgba,
+    ) -> Self {
+        Self {
+            transform: Default::default(),
+            camera_type: CameraType::Custom(projection_matrix),
+            clear_color,
+            culling_mask: u32::MAX,
+            depth_mode: DepthMode::Standard,
+            clear_flags: ClearFlags::Color,
+            priority: 0,
+            jitter_enabled: false,
             screen_size,
+            viewport_position: Vector2::zeros(),
+            viewport_size: screen_size,
+            pixel_scale: 1,
+            framebuffer_size: screen_size,
+            fov_animation: None,
+            frame_index: 0,
+            jitter: Vector2::zeros(),
+            view_projection: Matrix4::identity(),
+            previous_view_projection: Matrix4::identity(),
         }
     }
 
+    /// An off-axis perspective camera, the frustum edges are given at the near clipping plane
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_frustum(
+        left: f32,
+        right: f32,
+        bottom: f32,
+        top: f32,
+        screen_size: Vector2<u32>,
+        near_clipping_plane: f32,
+        far_clipping_plane: f32,
+        clear_color: LinSrgba,
+    ) -> Self {
+        Self::new_custom(
+            Self::frustum_matrix(
+                left,
+                right,
+                bottom,
+                top,
+                near_clipping_plane,
+                far_clipping_plane,
+            ),
+            screen_size,
+            clear_color,
+        )
+    }
+
+    /// Same as `glFrustum`
+    pub fn frustum_matrix(
+        left: f32,
+        right: f32,
+        bottom: f32,
+        top: f32,
+        near_clipping_plane: f32,
+        far_clipping_plane: f32,
+    ) -> Matrix4<f32> {
+        let (near, far) = (near_clipping_plane, far_clipping_plane);
+
+        Matrix4::new(
+            2.0 * near / (right - left),
+            0.0,
+            (right + left) / (right - left),
+            0.0,
+            0.0,
+            2.0 * near / (top - bottom),
+            (top + bottom) / (top - bottom),
+            0.0,
+            0.0,
+            0.0,
+            -(far + near) / (far - near),
+            -2.0 * far * near / (far - near),
+            0.0,
+            0.0,
+            -1.0,
+            0.0,
+        )
+    }
+
+    /// Like `frustum_matrix`, but maps the near plane to a depth of 1 and infinity to 0 for `DepthMode::ReverseZ`
+    pub fn reverse_z_frustum_matrix(
+        left: f32,
+        right: f32,
+        bottom: f32,
+        top: f32,
+        near_clipping_plane: f32,
+    ) -> Matrix4<f32> {
+        let near = near_clipping_plane;
+
+        Matrix4::new(
+            2.0 * near / (right - left),
+            0.0,
+            (right + left) / (right - left),
+            0.0,
+            0.0,
+            2.0 * near / (top - bottom),
+            (top + bottom) / (top - bottom),
+            0.0,
+            0.0,
+            0.0,
+            0.0,
+            near,
+            0.0,
+            0.0,
+            -1.0,
+            0.0,
+        )
+    }
+
     pub fn new_orthographic(
         orthographic_type: OrthographicType,
         screen_size: Vector2<u32>,
@@ -61,16 +326,8 @@ impl Camera {
         far_clipping_plane: f32,
         clear_color: LinSrgba,
     ) -> Self {
-        let (left, right, top, bottom) = match orthographic_type {
-            OrthographicType::UI { height } => {
-                let width = height * ((screen_size.x as f32) / (screen_size.y as f32));
-                (0.0, width, 0.0, height)
-            }
-            OrthographicType::World { height } => {
-                let width = height * ((screen_size.x as f32) / (screen_size.y as f32));
-                (-width / 2.0, width / 2.0, height / 2.0, -height / 2.0)
-            }
-        };
+        let (left, right, top, bottom) = orthographic_type.get_bounds(screen_size);
+        let (viewport_position, viewport_size) = orthographic_type.get_viewport(screen_size);
 
         Self {
             transform: Default::default(),
@@ -86,11 +343,60 @@ impl Camera {
                 orthographic_type,
             ),
             clear_color,
+            culling_mask: u32::MAX,
+            depth_mode: DepthMode::Standard,
+            clear_flags: ClearFlags::Color,
+            priority: 0,
+            jitter_enabled: false,
             screen_size: Vector2::new((left - right).abs() as u32, (top - bottom).abs() as u32),
+            viewport_position,
+            viewport_size,
+            pixel_scale: orthographic_type.get_pixel_scale(screen_size),
+            framebuffer_size: screen_size,
+            fov_animation: None,
+            frame_index: 0,
+            jitter: Vector2::zeros(),
+            view_projection: Matrix4::identity(),
+            previous_view_projection: Matrix4::identity(),
         }
     }
 
+    pub fn with_culling_mask(mut self, culling_mask: u32) -> Self {
+        self.culling_mask = culling_mask;
+        self
+    }
+
+    pub fn with_depth_mode(mut self, depth_mode: DepthMode) -> Self {
+        self.depth_mode = depth_mode;
+        self
+    }
+
+    pub fn with_clear_flags(mut self, clear_flags: ClearFlags) -> Self {
+        self.clear_flags = clear_flags;
+        self
+    }
+
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Orthographic cameras always use standard depth
+    pub fn is_reverse_z(&self) -> bool {
+        self.depth_mode == DepthMode::ReverseZ
+            && matches!(
+                self.camera_type,
+                CameraType::Perspective(_) | CameraType::Custom(_)
+            )
+    }
+
+    pub fn can_see(&self, object: &dyn GameObject) -> bool {
+        self.culling_mask & object.get_layer() != 0
+    }
+
     pub fn draw_objects(&self, objects: &mut [&mut dyn GameObject]) {
+        self.apply_render_state();
+
         if let CameraType::Orthographic(_, orthographic_type) = self.camera_type {
             if orthographic_type.is_ui() {
                 unsafe {
@@ -106,7 +412,9 @@ impl Camera {
                 });
 
                 for object in objects {
-                    object.draw(self);
+                    if self.can_see(*object) {
+                        object.draw(self);
+                    }
                 }
                 return;
             }
@@ -117,22 +425,181 @@ impl Camera {
         }
 
         for object in objects {
-            object.draw(self);
+            if self.can_see(*object) {
+                object.draw(self);
+            }
         }
     }
 
+    /// Includes the jitter when it's enabled
     pub fn get_projection_matrix(&self) -> Matrix4<f32> {
+        if self.jitter_enabled {
+            Matrix4::new_translation(&Vector3::new(self.jitter.x, self.jitter.y, 0.0))
+                * self.get_unjittered_projection_matrix()
+        } else {
+            self.get_unjittered_projection_matrix()
+        }
+    }
+
+    pub fn get_unjittered_projection_matrix(&self) -> Matrix4<f32> {
         match self.camera_type {
+            CameraType::Perspective(perspective) if self.is_reverse_z() => {
+                let top = perspective.znear() * (perspective.fovy() / 2.0).tan();
+                let right = top * perspective.aspect();
+                Self::reverse_z_frustum_matrix(-right, right, -top, top, perspective.znear())
+            }
             CameraType::Perspective(perspective) => perspective.into(),
             CameraType::Orthographic(orthographic, ..) => orthographic.into(),
+            CameraType::Custom(projection_matrix) => projection_matrix,
         }
     }
 
     pub fn get_transform_matrix(&self) -> Matrix4<f32> {
-        self.transform.to_matrix(true)
+        if self.is_pixel_perfect() {
+            let mut transform = self.transform;
+            transform.position = self.snap_to_pixel_grid(transform.position);
+            transform.to_view_matrix()
+        } else {
+            self.transform.to_view_matrix()
+        }
     }
 
+    /// The unjittered projection matrix multiplied by the view matrix
+    pub fn get_view_projection_matrix(&self) -> Matrix4<f32> {
+        self.get_unjittered_projection_matrix() * self.get_transform_matrix()
+    }
+
+    /// The unjittered view projection matrix from the previous `next_frame`, used to compute velocity
+    pub fn get_previous_view_projection_matrix(&self) -> Matrix4<f32> {
+        self.previous_view_projection
+    }
+
+    /// The current jitter offset in normalized device coordinates
+    pub fn get_jitter(&self) -> Vector2<f32> {
+        if self.jitter_enabled {
+            self.jitter
+        } else {
+            Vector2::zeros()
+        }
+    }
+
+    /// Call once per frame after moving the camera and before rendering.
+    /// Remembers the previous view projection matrix and advances the jitter.
+    pub fn next_frame(&mut self) {
+        let view_projection = self.get_view_projection_matrix();
+        self.previous_view_projection = if self.frame_index == 0 {
+            view_projection
+        } else {
+            self.view_projection
+        };
+        self.view_projection = view_projection;
+
+        self.frame_index = self.frame_index.wrapping_add(1).max(1);
+        let sample_index = (self.frame_index - 1) % JITTER_SAMPLES + 1;
+        self.jitter = Vector2::new(
+            (halton(sample_index, 2) - 0.5) * 2.0 / self.viewport_size.x as f32,
+            (halton(sample_index, 3) - 0.5) * 2.0 / self.viewport_size.y as f32,
+        );
+    }
+
+    pub fn is_pixel_perfect(&self) -> bool {
+        match self.camera_type {
+            CameraType::Orthographic(_, orthographic_type) => orthographic_type.is_pixel_perfect(),
+            _ => false,
+        }
+    }
+
+    /// The size of one screen pixel in world units, `None` if the camera isn't pixel perfect
+    pub fn get_pixel_size(&self) -> Option<f32> {
+        match self.camera_type {
+            CameraType::Orthographic(
+                _,
+                OrthographicType::PixelPerfect {
+                    pixels_per_unit,
+                    zoom,
+                    ..
+                },
+            ) => Some(1.0 / (pixels_per_unit * zoom * self.pixel_scale as f32)),
+            _ => None,
+        }
+    }
+
+    /// Rounds the x and y of `position` to the nearest screen pixel. Does nothing if the camera isn't pixel perfect.
+    pub fn snap_to_pixel_grid(&self, position: Vector3<f32>) -> Vector3<f32> {
+        match self.get_pixel_size() {
+            Some(pixel_size) => Vector3::new(
+                (position.x / pixel_size).round() * pixel_size,
+                (position.y / pixel_size).round() * pixel_size,
+                position.z,
+            ),
+            None => position,
+        }
+    }
+
+    pub fn get_zoom(&self) -> f32 {
+        match self.camera_type {
+            CameraType::Orthographic(_, OrthographicType::PixelPerfect { zoom, .. }) => zoom,
+            _ => 1.0,
+        }
+    }
+
+    /// Only affects pixel perfect cameras
+    pub fn set_zoom(&mut self, new_zoom: f32) {
+        if let CameraType::Orthographic(_, OrthographicType::PixelPerfect { zoom, .. }) =
+            &mut self.camera_type
+        {
+            *zoom = new_zoom;
+            self.update_orthographic_bounds();
+        }
+    }
+
+    /// Returns the `(position, size)` of the area the camera renders to
+    pub fn get_viewport(&self) -> (Vector2<i32>, Vector2<u32>) {
+        (self.viewport_position, self.viewport_size)
+    }
+
+    pub(crate) fn set_viewport(&mut self, position: Vector2<i32>, size: Vector2<u32>) {
+        self.viewport_position = position;
+        self.viewport_size = size;
+    }
+
+    pub(crate) fn apply_viewport(&self) {
+        unsafe {
+            gl::Viewport(
+                self.viewport_position.x,
+                self.viewport_position.y,
+                self.viewport_size.x as i32,
+                self.viewport_size.y as i32,
+            );
+        }
+    }
+
+    /// Sets the viewport, clip control and depth function for this camera, so a camera never
+    /// inherits them from whatever was drawn before it
+    fn apply_render_state(&self) {
+        self.apply_viewport();
+
+        unsafe {
+            if self.is_reverse_z() {
+                gl::ClipControl(gl::LOWER_LEFT, gl::ZERO_TO_ONE);
+                gl::DepthFunc(gl::GREATER);
+            } else {
+                gl::ClipControl(gl::LOWER_LEFT, gl::NEGATIVE_ONE_TO_ONE);
+                gl::DepthFunc(gl::LESS);
+            }
+        }
+    }
+
+    /// Clears according to the camera's clear flags
     pub fn clear(&self) {
+        let mask = match self.clear_flags {
+            ClearFlags::Color | ClearFlags::Skybox => gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT,
+            ClearFlags::DepthOnly => gl::DEPTH_BUFFER_BIT,
+            ClearFlags::Nothing => return,
+        };
+
+        self.apply_render_state();
+
         unsafe {
             gl::ClearColor(
                 self.clear_color.red,
@@ -140,31 +607,287 @@ impl Camera {
                 self.clear_color.blue,
                 self.clear_color.alpha,
             );
-            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+            gl::ClearDepth(if self.is_reverse_z() { 0.0 } else { 1.0 });
+            gl::Clear(mask);
         }
     }
 
-    pub fn set_screen_size(&mut self, screen_size: Vector2<u32>) {
+    /// Draws `skybox` without writing depth so everything drawn afterwards ends up in front of it.
+    /// The skybox's shader is responsible for ignoring the camera's translation.
+    pub fn draw_skybox(&self, skybox: &mut dyn GameObject) {
+        self.apply_render_state();
+
         unsafe {
-            gl::Viewport(0, 0, screen_size.x as i32, screen_size.y as i32);
+            gl::Disable(gl::DEPTH_TEST);
+            gl::DepthMask(gl::FALSE);
+        }
+
+        skybox.draw(self);
+
+        unsafe {
+            gl::DepthMask(gl::TRUE);
+        }
+    }
+
+    pub fn set_screen_size(&mut self, screen_size: Vector2<u32>) {
+        self.framebuffer_size = screen_size;
+        self.viewport_position = Vector2::zeros();
+        self.viewport_size = screen_size;
+        self.pixel_scale = 1;
+
+        match &mut self.camera_type {
+            CameraType::Perspective(perspective) => {
+                self.screen_size = screen_size;
+                perspective.set_aspect((screen_size.x as f32) / (screen_size.y as f32));
+            }
+            CameraType::Orthographic(..) => self.update_orthographic_bounds(),
+            CameraType::Custom(_) => self.screen_size = screen_size,
+        }
+
+        self.apply_viewport();
+    }
+
+    pub fn get_screen_size(&self) -> Vector2<u32> {
+        self.screen_size
+    }
+
+    /// Returns the vertical field of view in degrees, `None` if the camera isn't perspective
+    pub fn get_fov(&self) -> Option<f32> {
+        match self.camera_type {
+            CameraType::Perspective(perspective) => Some(perspective.fovy().to_degrees()),
+            _ => None,
         }
+    }
+
+    /// Sets the vertical field of view in degrees and cancels any fov animation. Only affects perspective cameras.
+    pub fn set_fov(&mut self, fov: f32) {
+        self.fov_animation = None;
+        self.set_fov_internal(fov);
+    }
 
+    fn set_fov_internal(&mut self, fov: f32) {
         if let CameraType::Perspective(perspective) = &mut self.camera_type {
-            self.screen_size = screen_size;
-            perspective.set_aspect((screen_size.x as f32) / (screen_size.y as f32));
-        } else if let CameraType::Orthographic(orthographic, orthographic_type) =
-            &mut self.camera_type
+            perspective.set_fovy(fov.to_radians());
+        }
+    }
+
+    /// Smoothly changes the fov to `target_fov` (in degrees) over `duration` seconds. Driven by `update`.
+    pub fn animate_fov(&mut self, target_fov: f32, duration: f32) {
+        if let Some(start_fov) = self.get_fov() {
+            if duration <= 0.0 {
+                self.set_fov(target_fov);
+                return;
+            }
+
+            self.fov_animation = Some(FovAnimation {
+                start_fov,
+                target_fov,
+                duration,
+                elapsed: 0.0,
+            });
+        }
+    }
+
+    pub fn is_animating_fov(&self) -> bool {
+        self.fov_animation.is_some()
+    }
+
+    /// Advances any running camera animations
+    pub fn update(&mut self, delta_time: f64) {
+        if let Some(mut animation) = self.fov_animation {
+            animation.elapsed += delta_time as f32;
+            let t = (animation.elapsed / animation.duration).min(1.0);
+            let t = t * t * (3.0 - 2.0 * t);
+
+            self.set_fov_internal(
+                animation.start_fov + (animation.target_fov - animation.start_fov) * t,
+            );
+            self.fov_animation = if animation.elapsed >= animation.duration {
+                None
+            } else {
+                Some(animation)
+            };
+        }
+    }
+
+    /// Custom projections are assumed to be a standard perspective or orthographic matrix, or a
+    /// reverse-Z frustum for reverse-Z cameras
+    pub fn get_near_clipping_plane(&self) -> f32 {
+        match self.camera_type {
+            CameraType::Perspective(perspective) => perspective.znear(),
+            CameraType::Orthographic(orthographic, ..) => orthographic.znear(),
+            CameraType::Custom(projection_matrix) if self.is_reverse_z() => {
+                projection_matrix[(2, 3)]
+            }
+            CameraType::Custom(projection_matrix) => {
+                Self::custom_clipping_planes(&projection_matrix).0
+            }
+        }
+    }
+
+    /// Doesn't affect custom cameras
+    pub fn set_near_clipping_plane(&mut self, near_clipping_plane: f32) {
+        match &mut self.camera_type {
+            CameraType::Perspective(perspective) => perspective.set_znear(near_clipping_plane),
+            CameraType::Orthographic(orthographic, ..) => {
+                orthographic.set_znear(near_clipping_plane)
+            }
+            CameraType::Custom(_) => (),
+        }
+    }
+
+    /// Infinite for reverse z cameras
+    pub fn get_far_clipping_plane(&self) -> f32 {
+        if self.is_reverse_z() {
+            return f32::INFINITY;
+        }
+
+        match self.camera_type {
+            CameraType::Perspective(perspective) => perspective.zfar(),
+            CameraType::Orthographic(orthographic, ..) => orthographic.zfar(),
+            CameraType::Custom(projection_matrix) => {
+                Self::custom_clipping_planes(&projection_matrix).1
+            }
+        }
+    }
+
+    /// Doesn't affect custom cameras
+    pub fn set_far_clipping_plane(&mut self, far_clipping_plane: f32) {
+        match &mut self.camera_type {
+            CameraType::Perspective(perspective) => perspective.set_zfar(far_clipping_plane),
+            CameraType::Orthographic(orthographic, ..) => orthographic.set_zfar(far_clipping_plane),
+            CameraType::Custom(_) => (),
+        }
+    }
+
+    /// Returns `(near, far)`
+    fn custom_clipping_planes(projection_matrix: &Matrix4<f32>) -> (f32, f32) {
+        let (m22, m23) = (projection_matrix[(2, 2)], projection_matrix[(2, 3)]);
+
+        if projection_matrix[(3, 2)] == 0.0 {
+            ((m23 + 1.0) / m22, (m23 - 1.0) / m22)
+        } else {
+            (m23 / (m22 - 1.0), m23 / (m22 + 1.0))
+        }
+    }
+
+    /// Returns the height of `UI` and `World` orthographic cameras
+    pub fn get_orthographic_height(&self) -> Option<f32> {
+        match self.camera_type {
+            CameraType::Orthographic(
+                _,
+                OrthographicType::UI { height } | OrthographicType::World { height },
+            ) => Some(height),
+            _ => None,
+        }
+    }
+
+    /// Only affects `UI` and `World` orthographic cameras
+    pub fn set_orthographic_height(&mut self, new_height: f32) {
+        if let CameraType::Orthographic(
+            _,
+            OrthographicType::UI { height } | OrthographicType::World { height },
+        ) = &mut self.camera_type
         {
-            let (left, right, top, bottom) = match *orthographic_type {
-                OrthographicType::UI { height } => {
-                    let width = height * ((screen_size.x as f32) / (screen_size.y as f32));
-                    (0.0, width, 0.0, height)
-                }
-                OrthographicType::World { height } => {
-                    let width = height * ((screen_size.x as f32) / (screen_size.y as f32));
-                    (-width / 2.0, width / 2.0, height / 2.0, -height / 2.0)
+            *height = new_height;
+            self.update_orthographic_bounds();
+        }
+    }
+
+    /// Width divided by height of the projection
+    pub fn get_aspect(&self) -> f32 {
+        match self.camera_type {
+            CameraType::Perspective(perspective) => perspective.aspect(),
+            CameraType::Orthographic(orthographic, ..) => {
+                (orthographic.right() - orthographic.left())
+                    / (orthographic.top() - orthographic.bottom()).abs()
+            }
+            CameraType::Custom(projection_matrix) => {
+                projection_matrix[(1, 1)] / projection_matrix[(0, 0)]
+            }
+        }
+    }
+
+    /// Overrides the aspect until the next `set_screen_size`. Orthographic cameras keep their height
+    /// and center (UI cameras keep their left edge at 0). Doesn't affect custom cameras.
+    pub fn set_aspect(&mut self, aspect: f32) {
+        match &mut self.camera_type {
+            CameraType::Perspective(perspective) => perspective.set_aspect(aspect),
+            CameraType::Orthographic(orthographic, orthographic_type) => {
+                let width = (orthographic.top() - orthographic.bottom()).abs() * aspect;
+
+                if orthographic_type.is_ui() {
+                    orthographic.set_left_and_right(0.0, width);
+                } else {
+                    let center = (orthographic.left() + orthographic.right()) / 2.0;
+                    orthographic.set_left_and_right(center - width / 2.0, center + width / 2.0);
                 }
-            };
+                self.screen_size.x = width.abs() as u32;
+            }
+            CameraType::Custom(_) => (),
+        }
+    }
+
+    /// Converts a screen position in pixels (origin at the top left, like `Game::get_mouse_position`)
+    /// and a depth buffer value into world space. Returns `None` for points at infinity.
+    pub fn screen_to_world_point(
+        &self,
+        screen_position: Vector2<f32>,
+        depth: f32,
+    ) -> Option<Vector3<f32>> {
+        let ndc_x = ((screen_position.x - self.viewport_position.x as f32)
+            / self.viewport_size.x as f32)
+            * 2.0
+            - 1.0;
+        let ndc_y = 1.0
+            - ((screen_position.y
+                - (self.framebuffer_size.y as i32
+                    - self.viewport_position.y
+                    - self.viewport_size.y as i32) as f32)
+                / self.viewport_size.y as f32)
+                * 2.0;
+        let ndc_z = if self.is_reverse_z() {
+            depth
+        } else {
+            depth * 2.0 - 1.0
+        };
+
+        self.ndc_to_world_point(&Vector3::new(ndc_x, ndc_y, ndc_z))
+    }
+
+    /// A world space ray from the near plane through a screen position in pixels, for picking
+    pub fn screen_point_to_ray(&self, screen_position: Vector2<f32>) -> Option<Ray> {
+        let near_depth = if self.is_reverse_z() { 1.0 } else { 0.0 };
+        let near = self.screen_to_world_point(screen_position, near_depth)?;
+        // Halfway is finite with reverse-Z too, unlike the far plane
+        let middle = self.screen_to_world_point(screen_position, 0.5)?;
+
+        Some(Ray::new(near, middle - near))
+    }
+
+    /// Returns `None` for points at infinity
+    pub fn ndc_to_world_point(&self, ndc: &Vector3<f32>) -> Option<Vector3<f32>> {
+        let inverse = self.get_view_projection_matrix().try_inverse()?;
+        let point = inverse * Vector4::new(ndc.x, ndc.y, ndc.z, 1.0);
+
+        if point.w.abs() <= f32::EPSILON {
+            None
+        } else {
+            Some(point.xyz() / point.w)
+        }
+    }
+
+    /// Rotates the camera to face `target`
+    pub fn look_at(&mut self, target: &Vector3<f32>, up: &Vector3<f32>) {
+        self.transform.look_at(target, up);
+    }
+
+    fn update_orthographic_bounds(&mut self) {
+        if let CameraType::Orthographic(orthographic, orthographic_type) = &mut self.camera_type {
+            let (left, right, top, bottom) = orthographic_type.get_bounds(self.framebuffer_size);
+            (self.viewport_position, self.viewport_size) =
+                orthographic_type.get_viewport(self.framebuffer_size);
+            self.pixel_scale = orthographic_type.get_pixel_scale(self.framebuffer_size);
 
             self.screen_size =
                 Vector2::new((left - right).abs() as u32, (top - bottom).abs() as u32);
@@ -173,8 +896,4 @@ impl Camera {
             orthographic.set_bottom_and_top(bottom, top);
         }
     }
-
-    pub fn get_screen_size(&self) -> Vector2<u32> {
-        self.screen_size
-    }
 }
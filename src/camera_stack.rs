@@ -0,0 +1,47 @@
+use crate::{Camera, ClearFlags, GameObject};
+
+/// Renders several cameras over the same objects in one call, ordered by their priority.
+/// Each camera clears with its own clear flags, draws into its own viewport and only draws the objects
+/// its culling mask can see.
+#[derive(Default)]
+pub struct CameraStack<'a> {
+    entries: Vec<(&'a Camera, Option<&'a mut dyn GameObject>)>,
+}
+
+impl<'a> CameraStack<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_camera(mut self, camera: &'a Camera) -> Self {
+        self.entries.push((camera, None));
+        self
+    }
+
+    /// The skybox is only drawn if the camera uses `ClearFlags::Skybox`
+    pub fn with_skybox_camera(
+        mut self,
+        camera: &'a Camera,
+        skybox: &'a mut dyn GameObject,
+    ) -> Self {
+        self.entries.push((camera, Some(skybox)));
+        self
+    }
+
+    pub fn render(&mut self, objects: &mut [&mut dyn GameObject]) {
+        // Stable so cameras with the same priority keep the order they were added in
+        self.entries.sort_by_key(|(camera, _)| camera.priority);
+
+        for (camera, skybox) in &mut self.entries {
+            camera.clear();
+
+            if camera.clear_flags == ClearFlags::Skybox {
+                if let Some(skybox) = skybox {
+                    camera.draw_skybox(*skybox);
+                }
+            }
+
+            camera.draw_objects(objects);
+        }
+    }
+}
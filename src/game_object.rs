@@ -4,4 +4,11 @@ pub trait GameObject {
     fn draw(&mut self, camera: &Camera);
     fn get_transform(&self) -> Transform;
     fn get_transform_matrix(&self) -> Matrix4<f32>;
+    /// The layer bitmask of this object. It is drawn by any camera whose culling mask shares a bit with it.
+    fn get_layer(&self) -> u32 {
+        DEFAULT_LAYER
+    }
 }
+
+/// The layer every object starts on.
+pub const DEFAULT_LAYER: u32 = 1;
@@ -0,0 +1,697 @@
+use crate::{
+    na::{Quaternion, UnitQuaternion, Vector2, Vector3, Vector4},
+    normals,
+    resources::{load_resource, ModelError, ResourceProvider},
+    tangents, Camera, DepthMode, FilterMode, Mesh, MeshData, NodeId, OrthographicType,
+    TangentVertex, Texture, Topology, Transform, TransformHierarchy, UsageType, Vertex, WrapMode,
+};
+use base64::Engine;
+use gltf::{
+    camera::Projection,
+    khr_lights_punctual::Kind,
+    mesh::Mode,
+    texture::{MagFilter, MinFilter, WrappingMode},
+};
+use palette::{LinSrgb, LinSrgba};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlphaMode {
+    Opaque,
+    /// Fully transparent below `PbrMaterial::alpha_cutoff`, opaque otherwise
+    Mask,
+    Blend,
+}
+
+/// A metallic-roughness material. Textures are indices into `GltfModel::textures`.
+#[derive(Debug, Clone)]
+pub struct PbrMaterial {
+    pub name: Option<String>,
+    pub base_color: LinSrgba,
+    pub base_color_texture: Option<usize>,
+    pub metallic: f32,
+    pub roughness: f32,
+    /// Roughness in the green channel and metalness in the blue channel
+    pub metallic_roughness_texture: Option<usize>,
+    pub normal_texture: Option<usize>,
+    pub normal_scale: f32,
+    pub occlusion_texture: Option<usize>,
+    pub occlusion_strength: f32,
+    pub emissive: LinSrgb,
+    pub emissive_texture: Option<usize>,
+    pub alpha_mode: AlphaMode,
+    pub alpha_cutoff: f32,
+    pub double_sided: bool,
+}
+
+impl Default for PbrMaterial {
+    fn default() -> Self {
+        Self {
+            name: None,
+            base_color: LinSrgba::new(1.0, 1.0, 1.0, 1.0),
+            base_color_texture: None,
+            metallic: 1.0,
+            roughness: 1.0,
+            metallic_roughness_texture: None,
+            normal_texture: None,
+            normal_scale: 1.0,
+            occlusion_texture: None,
+            occlusion_strength: 1.0,
+            emissive: LinSrgb::new(0.0, 0.0, 0.0),
+            emissive_texture: None,
+            alpha_mode: AlphaMode::Opaque,
+            alpha_cutoff: 0.5,
+            double_sided: false,
+        }
+    }
+}
+
+pub struct GltfPrimitive {
+    pub mesh: Mesh<TangentVertex>,
+    /// Into `GltfModel::materials`, `None` for the default material
+    pub material: Option<usize>,
+}
+
+pub struct GltfMesh {
+    pub name: Option<String>,
+    pub primitives: Vec<GltfPrimitive>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GltfProjection {
+    Perspective {
+        /// In radians
+        yfov: f32,
+        aspect_ratio: Option<f32>,
+        znear: f32,
+        /// `None` for an infinite far plane
+        zfar: Option<f32>,
+    },
+    Orthographic {
+        xmag: f32,
+        ymag: f32,
+        znear: f32,
+        zfar: f32,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct GltfCamera {
+    pub name: Option<String>,
+    pub projection: GltfProjection,
+}
+
+impl GltfCamera {
+    /// The aspect ratio follows `screen_size`. Infinite perspective cameras use `DepthMode::ReverseZ`.
+    pub fn to_camera(
+        &self,
+        transform: Transform,
+        screen_size: Vector2<u32>,
+        clear_color: LinSrgba,
+    ) -> Camera {
+        let mut camera = match self.projection {
+            GltfProjection::Perspective {
+                yfov, znear, zfar, ..
+            } => {
+                let camera = Camera::new_perspective(
+                    yfov.to_degrees(),
+                    screen_size,
+                    znear,
+                    zfar.unwrap_or(znear * 1000.0),
+                    clear_color,
+                );
+
+                if zfar.is_none() {
+                    camera.with_depth_mode(DepthMode::ReverseZ)
+                } else {
+                    camera
+                }
+            }
+            GltfProjection::Orthographic {
+                ymag, znear, zfar, ..
+            } => Camera::new_orthographic(
+                OrthographicType::World { height: ymag * 2.0 },
+                screen_size,
+                znear,
+                zfar,
+                clear_color,
+            ),
+        };
+        camera.transform = transform;
+
+        camera
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LightKind {
+    Directional,
+    Point,
+    /// Angles in radians from the center of the cone
+    Spot {
+        inner_cone_angle: f32,
+        outer_cone_angle: f32,
+    },
+}
+
+/// A `KHR_lights_punctual` light. Directional and spot lights shine along the node's forward.
+#[derive(Debug, Clone)]
+pub struct GltfLight {
+    pub name: Option<String>,
+    pub kind: LightKind,
+    pub color: LinSrgb,
+    /// In candela for point and spot lights and lux for directional lights
+    pub intensity: f32,
+    /// `None` for an infinite range
+    pub range: Option<f32>,
+}
+
+/// Indices into the lists of `GltfModel`
+#[derive(Debug, Clone)]
+pub struct GltfNode {
+    pub name: Option<String>,
+    pub id: NodeId,
+    pub mesh: Option<usize>,
+    pub camera: Option<usize>,
+    pub light: Option<usize>,
+}
+
+/// Texture coordinates start at the bottom left of the images, like `primitives` and `ObjModel`
+pub struct GltfModel {
+    /// Every node of the file, parented like in the file
+    pub hierarchy: TransformHierarchy,
+    /// In file order
+    pub nodes: Vec<GltfNode>,
+    /// The root nodes of the default scene, or the first scene
+    pub scene_roots: Vec<usize>,
+    pub meshes: Vec<GltfMesh>,
+    pub materials: Vec<PbrMaterial>,
+    /// One per glTF texture, so every image and sampler combination gets its own
+    pub textures: Vec<Texture>,
+    pub cameras: Vec<GltfCamera>,
+    pub lights: Vec<GltfLight>,
+}
+
+impl GltfModel {
+    /// Loads `.gltf` and `.glb` files. External buffers and images are resolved relative to the file.
+    pub fn load(
+        path: impl AsRef<Path>,
+        resources: &impl ResourceProvider,
+        usage_type: UsageType,
+    ) -> Result<Self, ModelError> {
+        let path = path.as_ref();
+        let directory = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        let gltf =
+            gltf::Gltf::from_slice(&load_resource(resources, path)?).map_err(ModelError::Gltf)?;
+        let document = &gltf.document;
+
+        let buffers = document
+            .buffers()
+            .map(|buffer| {
+                let mut data = match buffer.source() {
+                    gltf::buffer::Source::Bin => gltf.blob.clone().ok_or_else(|| {
+                        ModelError::Invalid("The binary chunk is missing".to_string())
+                    })?,
+                    gltf::buffer::Source::Uri(uri) => load_uri(resources, &directory, uri)?,
+                };
+
+                if data.len() < buffer.length() {
+                    return Err(ModelError::Invalid(format!(
+                        "Buffer {} is shorter than its declared length",
+                        buffer.index()
+                    )));
+                }
+                // The binary chunk is padded to 4 bytes
+                data.truncate(buffer.length());
+
+                Ok(data)
+            })
+            .collect::<Result<Vec<_>, ModelError>>()?;
+
+        let textures = document
+            .textures()
+            .map(|texture| {
+                let (data, image_path) = match texture.source().source() {
+                    gltf::image::Source::View { view, .. } => {
+                        let buffer = &buffers[view.buffer().index()];
+                        let data = buffer
+                            .get(view.offset()..view.offset() + view.length())
+                            .ok_or_else(|| {
+                                ModelError::Invalid(format!(
+                                    "Image view {} is out of bounds",
+                                    view.index()
+                                ))
+                            })?;
+                        (data.to_vec(), path.to_path_buf())
+                    }
+                    gltf::image::Source::Uri { uri, .. } => {
+                        (load_uri(resources, &directory, uri)?, directory.join(uri))
+                    }
+                };
+                let image = image::load_from_memory(&data)
+                    .map_err(|error| ModelError::Image(image_path, error))?;
+
+                let sampler = texture.sampler();
+                // Matches the flipped texture coordinates, see `load_primitive`
+                let result = Texture::from_image(
+                    image.flipv(),
+                    wrap_mode(sampler.wrap_s()),
+                    sampler
+                        .min_filter()
+                        .map_or(FilterMode::LinearMipMapLinear, min_filter_mode),
+                    match sampler.mag_filter() {
+                        Some(MagFilter::Nearest) => FilterMode::Nearest,
+                        _ => FilterMode::Linear,
+                    },
+                );
+                result.set_wrap_modes(wrap_mode(sampler.wrap_s()), wrap_mode(sampler.wrap_t()));
+
+                Ok(result)
+            })
+            .collect::<Result<Vec<_>, ModelError>>()?;
+
+        let materials = document
+            .materials()
+            .map(|material| {
+                let pbr = material.pbr_metallic_roughness();
+                let [r, g, b, a] = pbr.base_color_factor();
+                let [emissive_r, emissive_g, emissive_b] = material.emissive_factor();
+
+                PbrMaterial {
+                    name: material.name().map(str::to_string),
+                    base_color: LinSrgba::new(r, g, b, a),
+                    base_color_texture: pbr.base_color_texture().map(|info| info.texture().index()),
+                    metallic: pbr.metallic_factor(),
+                    roughness: pbr.roughness_factor(),
+                    metallic_roughness_texture: pbr
+                        .metallic_roughness_texture()
+                        .map(|info| info.texture().index()),
+                    normal_texture: material
+                        .normal_texture()
+                        .map(|normal| normal.texture().index()),
+                    normal_scale: material
+                        .normal_texture()
+                        .map_or(1.0, |normal| normal.scale()),
+                    occlusion_texture: material
+                        .occlusion_texture()
+                        .map(|occlusion| occlusion.texture().index()),
+                    occlusion_strength: material
+                        .occlusion_texture()
+                        .map_or(1.0, |occlusion| occlusion.strength()),
+                    emissive: LinSrgb::new(emissive_r, emissive_g, emissive_b),
+                    emissive_texture: material
+                        .emissive_texture()
+                        .map(|info| info.texture().index()),
+                    alpha_mode: match material.alpha_mode() {
+                        gltf::material::AlphaMode::Opaque => AlphaMode::Opaque,
+                        gltf::material::AlphaMode::Mask => AlphaMode::Mask,
+                        gltf::material::AlphaMode::Blend => AlphaMode::Blend,
+                    },
+                    alpha_cutoff: material.alpha_cutoff().unwrap_or(0.5),
+                    double_sided: material.double_sided(),
+                }
+            })
+            .collect();
+
+        let meshes = document
+            .meshes()
+            .map(|mesh| {
+                Ok(GltfMesh {
+                    name: mesh.name().map(str::to_string),
+                    primitives: mesh
+                        .primitives()
+                        .map(|primitive| {
+                            Ok(GltfPrimitive {
+                                mesh: load_primitive(&primitive, &buffers, usage_type)?,
+                                material: primitive.material().index(),
+                            })
+                        })
+                        .collect::<Result<Vec<_>, ModelError>>()?,
+                })
+            })
+            .collect::<Result<Vec<_>, ModelError>>()?;
+
+        let cameras = document
+            .cameras()
+            .map(|camera| GltfCamera {
+                name: camera.name().map(str::to_string),
+                projection: match camera.projection() {
+                    Projection::Perspective(perspective) => GltfProjection::Perspective {
+                        yfov: perspective.yfov(),
+                        aspect_ratio: perspective.aspect_ratio(),
+                        znear: perspective.znear(),
+                        zfar: perspective.zfar(),
+                    },
+                    Projection::Orthographic(orthographic) => GltfProjection::Orthographic {
+                        xmag: orthographic.xmag(),
+                        ymag: orthographic.ymag(),
+                        znear: orthographic.znear(),
+                        zfar: orthographic.zfar(),
+                    },
+                },
+            })
+            .collect();
+
+        let lights = document
+            .lights()
+            .into_iter()
+            .flatten()
+            .map(|light| {
+                let [r, g, b] = light.color();
+
+                GltfLight {
+                    name: light.name().map(str::to_string),
+                    kind: match light.kind() {
+                        Kind::Directional => LightKind::Directional,
+                        Kind::Point => LightKind::Point,
+                        Kind::Spot {
+                            inner_cone_angle,
+                            outer_cone_angle,
+                        } => LightKind::Spot {
+                            inner_cone_angle,
+                            outer_cone_angle,
+                        },
+                    },
+                    color: LinSrgb::new(r, g, b),
+                    intensity: light.intensity(),
+                    range: light.range(),
+                }
+            })
+            .collect();
+
+        let mut hierarchy = TransformHierarchy::new();
+        let nodes = document
+            .nodes()
+            .map(|node| {
+                let (position, [x, y, z, w], scale) = node.transform().decomposed();
+
+                GltfNode {
+                    name: node.name().map(str::to_string),
+                    id: hierarchy.add(Transform {
+                        position: Vector3::from(position),
+                        rotation: UnitQuaternion::from_quaternion(Quaternion::new(w, x, y, z)),
+                        scale: Vector3::from(scale),
+                    }),
+                    mesh: node.mesh().map(|mesh| mesh.index()),
+                    camera: node.camera().map(|camera| camera.index()),
+                    light: node.light().map(|light| light.index()),
+                }
+            })
+            .collect::<Vec<_>>();
+        for node in document.nodes() {
+            for child in node.children() {
+                let (child_id, parent_id) = (nodes[child.index()].id, nodes[node.index()].id);
+
+                if hierarchy.get_parent(child_id).is_some() {
+                    return Err(ModelError::Invalid(format!(
+                        "Node {} has several parents",
+                        child.index()
+                    )));
+                }
+                if hierarchy.is_ancestor_or_self(child_id, parent_id) {
+                    return Err(ModelError::Invalid(format!(
+                        "Node {} is its own ancestor",
+                        child.index()
+                    )));
+                }
+                hierarchy.set_parent(child_id, Some(parent_id));
+            }
+        }
+
+        let scene_roots = document
+            .default_scene()
+            .or_else(|| document.scenes().next())
+            .map(|scene| scene.nodes().map(|node| node.index()).collect())
+            .unwrap_or_default();
+
+        Ok(Self {
+            hierarchy,
+            nodes,
+            scene_roots,
+            meshes,
+            materials,
+            textures,
+            cameras,
+            lights,
+        })
+    }
+
+    pub fn get_texture(&self, index: Option<usize>) -> Option<&Texture> {
+        self.textures.get(index?)
+    }
+
+    /// The material of a primitive, the glTF default material if it has none
+    pub fn get_material(&self, primitive: &GltfPrimitive) -> PbrMaterial {
+        primitive
+            .material
+            .and_then(|index| self.materials.get(index))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// The camera of a node, placed at the node's world transform
+    pub fn get_camera(
+        &mut self,
+        node: usize,
+        screen_size: Vector2<u32>,
+        clear_color: LinSrgba,
+    ) -> Option<Camera> {
+        let node = &self.nodes[node];
+        let camera = &self.cameras[node.camera?];
+        let transform = Transform::from_matrix(&self.hierarchy.get_world_matrix(node.id));
+
+        Some(camera.to_camera(transform, screen_size, clear_color))
+    }
+}
+
+/// Reads data URIs directly and everything else through `resources`
+fn load_uri(
+    resources: &impl ResourceProvider,
+    directory: &Path,
+    uri: &str,
+) -> Result<Vec<u8>, ModelError> {
+    match uri.strip_prefix("data:") {
+        Some(data) => {
+            let (header, data) = data
+                .split_once(',')
+                .ok_or_else(|| ModelError::Invalid("Malformed data URI".to_string()))?;
+            if !header.ends_with(";base64") {
+                return Err(ModelError::Invalid(
+                    "Only base64 data URIs are supported".to_string(),
+                ));
+            }
+
+            base64::engine::general_purpose::STANDARD
+                .decode(data)
+                .map_err(|error| ModelError::Invalid(format!("Malformed data URI: {error}")))
+        }
+        None => load_resource(resources, &directory.join(percent_decode(uri))),
+    }
+}
+
+/// URIs escape characters like spaces in file names
+fn percent_decode(uri: &str) -> PathBuf {
+    let bytes = uri.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| uri.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    PathBuf::from(String::from_utf8_lossy(&decoded).into_owned())
+}
+
+fn wrap_mode(wrapping_mode: WrappingMode) -> WrapMode {
+    match wrapping_mode {
+        WrappingMode::ClampToEdge => WrapMode::ClampToEdge,
+        WrappingMode::MirroredRepeat => WrapMode::MirroredRepeat,
+        WrappingMode::Repeat => WrapMode::Repeat,
+    }
+}
+
+fn min_filter_mode(min_filter: MinFilter) -> FilterMode {
+    match min_filter {
+        MinFilter::Nearest => FilterMode::Nearest,
+        MinFilter::Linear => FilterMode::Linear,
+        MinFilter::NearestMipmapNearest => FilterMode::NearestMipMapNearest,
+        MinFilter::LinearMipmapNearest => FilterMode::LinearMipMapNearest,
+        MinFilter::NearestMipmapLinear => FilterMode::NearestMipMapLinear,
+        MinFilter::LinearMipmapLinear => FilterMode::LinearMipMapLinear,
+    }
+}
+
+/// Missing normals are generated flat and missing tangents with MikkTSpace, as the spec asks.
+/// glTF texture coordinates start at the top left, they are flipped to start at the bottom left
+/// like OBJ and `primitives`.
+fn load_primitive(
+    primitive: &gltf::Primitive,
+    buffers: &[Vec<u8>],
+    usage_type: UsageType,
+) -> Result<Mesh<TangentVertex>, ModelError> {
+    let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(Vec::as_slice));
+
+    let positions = reader
+        .read_positions()
+        .ok_or_else(|| ModelError::Invalid("A primitive has no positions".to_string()))?
+        .collect::<Vec<_>>();
+    let file_normals = reader.read_normals().map(Iterator::collect::<Vec<_>>);
+    let tex_coords = reader
+        .read_tex_coords(0)
+        .map(|tex_coords| tex_coords.into_f32().collect::<Vec<_>>());
+    let file_tangents = reader.read_tangents().map(Iterator::collect::<Vec<_>>);
+
+    let vertices = positions
+        .iter()
+        .enumerate()
+        .map(|(i, position)| {
+            Vertex::new(
+                Vector3::from(*position),
+                file_normals
+                    .as_ref()
+                    .and_then(|normals| normals.get(i))
+                    .map_or(Vector3::zeros(), |normal| Vector3::from(*normal)),
+                tex_coords
+                    .as_ref()
+                    .and_then(|tex_coords| tex_coords.get(i))
+                    .map_or(Vector2::zeros(), |tex_coord| {
+                        Vector2::new(tex_coord[0], 1.0 - tex_coord[1])
+                    }),
+            )
+        })
+        .collect::<Vec<_>>();
+    let data = MeshData {
+        vertices,
+        indices: reader
+            .read_indices()
+            .map(|indices| indices.into_u32().collect()),
+        topology: match primitive.mode() {
+            Mode::Points => Topology::Points,
+            Mode::Lines => Topology::Lines,
+            Mode::LineLoop => Topology::LineLoop,
+            Mode::LineStrip => Topology::LineStrip,
+            Mode::Triangles => Topology::Triangles,
+            Mode::TriangleStrip => Topology::TriangleStrip,
+            Mode::TriangleFan => Topology::TriangleFan,
+        },
+    };
+    data.validate()
+        .map_err(|error| ModelError::Invalid(format!("Invalid primitive: {error:?}")))?;
+
+    let is_triangles = matches!(
+        data.topology,
+        Topology::Triangles | Topology::TriangleStrip | Topology::TriangleFan
+    );
+    if let Some(tangents) = &file_tangents {
+        if tangents.len() != data.vertices.len() {
+            return Err(ModelError::Invalid(format!(
+                "A primitive has {} tangents for {} vertices",
+                tangents.len(),
+                data.vertices.len()
+            )));
+        }
+    }
+
+    let data = match file_tangents {
+        Some(tangents) if file_normals.is_some() || !is_triangles => MeshData {
+            vertices: data
+                .vertices
+                .iter()
+                .zip(tangents)
+                .map(|(vertex, tangent)| {
+                    TangentVertex::new(
+                        vertex.position,
+                        vertex.normal,
+                        vertex.tex_coord,
+                        Vector4::from(tangent),
+                    )
+                })
+                .collect(),
+            indices: data.indices,
+            topology: data.topology,
+        },
+        _ if is_triangles => {
+            let faces = data.get_triangles();
+            let (vertices, faces) = if file_normals.is_some() {
+                (data.vertices, faces)
+            } else {
+                normals::generate_flat_normals(&data.vertices, &faces)
+            };
+            let (vertices, faces) = tangents::generate_tangents(&vertices, &faces);
+
+            MeshData::from_faces(vertices, &faces)
+        }
+        _ => MeshData {
+            vertices: data
+                .vertices
+                .iter()
+                .map(|vertex| {
+                    TangentVertex::new(
+                        vertex.position,
+                        vertex.normal,
+                        vertex.tex_coord,
+                        Vector4::zeros(),
+                    )
+                })
+                .collect(),
+            indices: data.indices,
+            topology: data.topology,
+        },
+    };
+
+    Ok(Mesh::from_data(&data, usage_type))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn load_nodes(nodes: &str) -> Result<GltfModel, ModelError> {
+        let json = format!(r#"{{"asset": {{"version": "2.0"}}, "nodes": {nodes}}}"#);
+        let resources = HashMap::from([("model.gltf", json.into_bytes())]);
+
+        GltfModel::load("model.gltf", &resources, UsageType::Static)
+    }
+
+    #[test]
+    fn nodes_are_parented_like_in_the_file() {
+        let model = load_nodes(r#"[{"children": [1]}, {"translation": [0, 1, 0]}]"#).unwrap();
+
+        assert_eq!(
+            model.hierarchy.get_parent(model.nodes[1].id),
+            Some(model.nodes[0].id)
+        );
+    }
+
+    #[test]
+    fn cycles_are_errors() {
+        for nodes in [
+            r#"[{"children": [0]}]"#,
+            r#"[{"children": [1]}, {"children": [0]}]"#,
+        ] {
+            assert!(matches!(load_nodes(nodes), Err(ModelError::Invalid(_))));
+        }
+    }
+
+    #[test]
+    fn several_parents_are_errors() {
+        let result = load_nodes(r#"[{"children": [2]}, {"children": [2]}, {}]"#);
+
+        assert!(matches!(result, Err(ModelError::Invalid(_))));
+    }
+}
@@ -11,6 +11,7 @@ pub trait GUIObject: GameObject {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Alignment {
     TopLeft,
     TopCenter,
@@ -77,6 +78,7 @@ impl Alignment {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Size {
     Auto,
     Manual(Vector2<f32>),
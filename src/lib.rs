@@ -1,32 +1,66 @@
+mod bounds;
 mod camera;
+mod camera_stack;
 mod font;
 mod game;
 mod game_object;
+mod gltf_model;
 mod gui_object;
 mod mesh;
+mod mesh_data;
+mod mesh_export;
+mod mesh_import;
 mod mesh_object;
+pub mod normals;
+mod obj_model;
+pub mod primitives;
+mod raycast;
+mod render_target;
+mod resources;
 mod shader;
 mod shader_program;
+mod stereo_rig;
+mod taa;
+pub mod tangents;
 mod text_object;
 mod texture;
 mod transform;
+mod transform_hierarchy;
 pub mod utils;
 mod vertex;
+mod vertex_layout;
 
-pub use camera::{Camera, CameraType, OrthographicType};
+pub use bounds::{Aabb, BoundingSphere};
+pub use camera::{Camera, CameraType, ClearFlags, DepthMode, OrthographicType};
+pub use camera_stack::CameraStack;
 pub use font::Font;
 pub use game::Game;
-pub use game_object::GameObject;
+pub use game_object::{GameObject, DEFAULT_LAYER};
 pub use glfw::{self, Action, CursorMode as MouseMode, Key, WindowMode};
+pub use gltf_model::{
+    AlphaMode, GltfCamera, GltfLight, GltfMesh, GltfModel, GltfNode, GltfPrimitive, GltfProjection,
+    LightKind, PbrMaterial,
+};
 pub use gui_object::{Alignment, GUIObject, Size};
 pub use image;
-pub use mesh::{Mesh, UsageType};
+pub use mesh::{IndexFormat, Indices, Mesh, Topology, UsageType};
+pub use mesh_data::{MeshData, MeshDataError, MeshVertex};
+pub use mesh_export::{export_glb, ExportMesh, ObjExport};
+pub use mesh_import::ImportedMesh;
 pub use mesh_object::MeshObject;
 pub use nalgebra as na;
+pub use obj_model::{MaterialGroup, ObjLoadOptions, ObjMaterial, ObjModel};
 pub use palette;
+pub use raycast::{Bvh, Hit, Ray};
+pub use render_target::{DepthFormat, RenderTarget};
+pub use resources::{FileSystemProvider, ModelError, ResourceProvider};
 pub use shader::{Shader, ShaderType};
 pub use shader_program::{ProgramValue, ShaderProgram};
+pub use stereo_rig::{Eye, StereoRig};
+pub use taa::{TaaError, TaaResolver};
 pub use text_object::TextObject;
 pub use texture::{FilterMode, Texture, WrapMode};
-pub use transform::Transform;
-pub use vertex::Vertex;
+pub use transform::{Space, Transform};
+pub use transform_hierarchy::{NodeId, TransformHierarchy};
+pub use vertex::{ColorVertex, Instance, TangentVertex, Vertex};
+pub use vertex_layout::{AttributeType, VertexAttribute, VertexLayout};
@@ -1,44 +1,504 @@
 use crate::na::{Vector2, Vector3};
-use crate::{texture::Texture, vertex::Vertex};
-use std::ptr::null;
+use crate::{
+    normals::{self, NormalGeneration},
+    tangents,
+    texture::Texture,
+    vertex::{TangentVertex, Vertex},
+    Aabb, AttributeType, BoundingSphere, Bvh, Hit, MeshData, MeshDataError, MeshVertex, Ray,
+    VertexLayout,
+};
+use std::{marker::PhantomData, ptr::null};
 
 #[derive(Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum UsageType {
     Static = gl::STATIC_DRAW as isize,
     Dynamic = gl::DYNAMIC_DRAW as isize,
 }
 
-pub struct Mesh {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Topology {
+    Points = gl::POINTS as isize,
+    Lines = gl::LINES as isize,
+    LineStrip = gl::LINE_STRIP as isize,
+    LineLoop = gl::LINE_LOOP as isize,
+    Triangles = gl::TRIANGLES as isize,
+    TriangleStrip = gl::TRIANGLE_STRIP as isize,
+    TriangleFan = gl::TRIANGLE_FAN as isize,
+}
+
+/// The indices of a mesh, grouped to match the topology
+#[derive(Debug, Clone, Copy)]
+pub enum Indices<'a> {
+    Points(&'a [u32]),
+    Lines(&'a [Vector2<u32>]),
+    LineStrip(&'a [u32]),
+    LineLoop(&'a [u32]),
+    Triangles(&'a [Vector3<u32>]),
+    TriangleStrip(&'a [u32]),
+    TriangleFan(&'a [u32]),
+}
+
+impl<'a> Indices<'a> {
+    pub fn get_topology(&self) -> Topology {
+        match self {
+            Indices::Points(_) => Topology::Points,
+            Indices::Lines(_) => Topology::Lines,
+            Indices::LineStrip(_) => Topology::LineStrip,
+            Indices::LineLoop(_) => Topology::LineLoop,
+            Indices::Triangles(_) => Topology::Triangles,
+            Indices::TriangleStrip(_) => Topology::TriangleStrip,
+            Indices::TriangleFan(_) => Topology::TriangleFan,
+        }
+    }
+
+    /// The indices as they are laid out in the index buffer
+    pub fn as_flat(&self) -> &'a [u32] {
+        match *self {
+            Indices::Lines(lines) => unsafe {
+                std::slice::from_raw_parts(lines.as_ptr() as *const u32, lines.len() * 2)
+            },
+            Indices::Triangles(faces) => unsafe {
+                std::slice::from_raw_parts(faces.as_ptr() as *const u32, faces.len() * 3)
+            },
+            Indices::Points(indices)
+            | Indices::LineStrip(indices)
+            | Indices::LineLoop(indices)
+            | Indices::TriangleStrip(indices)
+            | Indices::TriangleFan(indices) => indices,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IndexFormat {
+    U16 = gl::UNSIGNED_SHORT as isize,
+    U32 = gl::UNSIGNED_INT as isize,
+}
+
+impl IndexFormat {
+    pub fn for_vertex_count(vertex_count: usize) -> Self {
+        if vertex_count <= u16::MAX as usize + 1 {
+            IndexFormat::U16
+        } else {
+            IndexFormat::U32
+        }
+    }
+
+    /// Also uses 32 bits if an index doesn't fit 16, so out of bounds indices aren't truncated
+    pub fn for_indices(vertex_count: usize, indices: &[u32]) -> Self {
+        match Self::for_vertex_count(vertex_count) {
+            IndexFormat::U16 if indices.iter().any(|&index| index > u16::MAX as u32) => {
+                IndexFormat::U32
+            }
+            index_format => index_format,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        match self {
+            IndexFormat::U16 => std::mem::size_of::<u16>(),
+            IndexFormat::U32 => std::mem::size_of::<u32>(),
+        }
+    }
+}
+
+pub struct Mesh<V: VertexLayout = Vertex> {
     vao: u32,
     vbo: u32,
     ebo: u32,
+    instance_vbo: u32,
+    /// In bytes
     vertex_buffer_size: usize,
-    face_buffer_size: usize,
-    total_faces: usize,
+    /// In bytes
+    index_buffer_size: usize,
+    total_vertices: usize,
+    total_indices: usize,
+    index_format: Option<IndexFormat>,
+    topology: Topology,
     usage_type: UsageType,
+    data: Option<MeshData<V>>,
+    /// Built from `data`
+    bvh: Option<Bvh>,
+    aabb: Aabb,
+    bounding_sphere: BoundingSphere,
+    vertex_type: PhantomData<V>,
 }
 
-impl Mesh {
+impl<V: VertexLayout> Mesh<V> {
     pub fn draw(&self, textures: &[&Texture]) {
+        Self::bind_textures(textures);
+
+        unsafe {
+            gl::BindVertexArray(self.vao);
+
+            match self.index_format {
+                Some(index_format) => gl::DrawElements(
+                    self.topology as u32,
+                    self.total_indices as i32,
+                    index_format as u32,
+                    null(),
+                ),
+                None => gl::DrawArrays(self.topology as u32, 0, self.total_vertices as i32),
+            }
+        }
+    }
+
+    /// Draws the mesh once per instance in a single draw call. The instance attributes are streamed
+    /// to the GPU every call and bound to the locations after the vertex attributes, so with
+    /// `Vertex` and `Instance` the shader declares `layout(location = 3) in mat4 model;` and
+    /// `layout(location = 7) in vec4 color;`.
+    pub fn draw_instanced<I: VertexLayout>(&self, textures: &[&Texture], instances: &[I]) {
+        if instances.is_empty() {
+            return;
+        }
+        I::assert_layout();
+
+        Self::bind_textures(textures);
+
+        unsafe {
+            gl::BindVertexArray(self.vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.instance_vbo);
+            // Orphans the previous buffer so the driver doesn't wait for draws still using it
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                std::mem::size_of_val(instances) as isize,
+                null(),
+                gl::STREAM_DRAW,
+            );
+            gl::BufferSubData(
+                gl::ARRAY_BUFFER,
+                0,
+                std::mem::size_of_val(instances) as isize,
+                instances.as_ptr() as *const gl::types::GLvoid,
+            );
+        }
+
+        set_attribute_pointers::<I>(V::ATTRIBUTES.len() as u32, 1);
+
+        unsafe {
+            match self.index_format {
+                Some(index_format) => gl::DrawElementsInstanced(
+                    self.topology as u32,
+                    self.total_indices as i32,
+                    index_format as u32,
+                    null(),
+                    instances.len() as i32,
+                ),
+                None => gl::DrawArraysInstanced(
+                    self.topology as u32,
+                    0,
+                    self.total_vertices as i32,
+                    instances.len() as i32,
+                ),
+            }
+        }
+    }
+
+    fn bind_textures(textures: &[&Texture]) {
         for (i, texture) in textures.iter().enumerate() {
             unsafe {
                 gl::ActiveTexture(gl::TEXTURE0 + (i as u32));
                 gl::BindTexture(gl::TEXTURE_2D, texture.get_id());
             }
         }
+    }
+
+    pub fn from_vertices(vertices: &[V], faces: &[Vector3<u32>], usage_type: UsageType) -> Self {
+        Self::from_indexed_vertices(vertices, Indices::Triangles(faces), usage_type)
+    }
+
+    /// Uses 16-bit indices when every vertex can be addressed by them
+    pub fn from_indexed_vertices(vertices: &[V], indices: Indices, usage_type: UsageType) -> Self {
+        Self::new(
+            vertices,
+            Some(indices.as_flat()),
+            indices.get_topology(),
+            usage_type,
+        )
+    }
+
+    /// Draws the vertices in order with `DrawArrays`
+    pub fn from_non_indexed_vertices(
+        vertices: &[V],
+        topology: Topology,
+        usage_type: UsageType,
+    ) -> Self {
+        Self::new(vertices, None, topology, usage_type)
+    }
+
+    pub fn from_data(data: &MeshData<V>, usage_type: UsageType) -> Self {
+        Self::new(
+            &data.vertices,
+            data.indices.as_deref(),
+            data.topology,
+            usage_type,
+        )
+    }
+
+    /// Keeps the data attached to the mesh for queries, updates keep it in sync.
+    /// The data is validated first since the queries index the vertices.
+    pub fn from_data_retained(
+        data: MeshData<V>,
+        usage_type: UsageType,
+    ) -> Result<Self, MeshDataError> {
+        data.validate()?;
+
+        let mut mesh = Self::from_data(&data, usage_type);
+        mesh.retain(data);
+
+        Ok(mesh)
+    }
+
+    fn new(
+        vertices: &[V],
+        indices: Option<&[u32]>,
+        topology: Topology,
+        usage_type: UsageType,
+    ) -> Self {
+        let mut mesh = Self {
+            vao: 0,
+            vbo: 0,
+            ebo: 0,
+            instance_vbo: 0,
+            vertex_buffer_size: 0,
+            index_buffer_size: 0,
+            total_vertices: 0,
+            total_indices: 0,
+            index_format: None,
+            topology,
+            usage_type,
+            data: None,
+            bvh: None,
+            aabb: Default::default(),
+            bounding_sphere: Default::default(),
+            vertex_type: PhantomData,
+        };
+        unsafe {
+            gl::GenVertexArrays(1, &mut mesh.vao);
+            gl::GenBuffers(1, &mut mesh.vbo);
+            gl::GenBuffers(1, &mut mesh.ebo);
+            gl::GenBuffers(1, &mut mesh.instance_vbo);
+        }
+
+        mesh.upload(vertices, indices);
+        set_attribute_pointers::<V>(0, 0);
+
+        mesh
+    }
+
+    /// Updates panic if the mesh retains its data and the new data doesn't pass `MeshData::validate`
+    pub fn update_vertices(&mut self, vertices: &[V], faces: &[Vector3<u32>]) {
+        self.update_indexed_vertices(vertices, Indices::Triangles(faces));
+    }
+
+    /// The topology and index format can differ from the current ones
+    pub fn update_indexed_vertices(&mut self, vertices: &[V], indices: Indices) {
+        self.topology = indices.get_topology();
+        self.upload(vertices, Some(indices.as_flat()));
+
+        if self.data.is_some() {
+            self.retain(MeshData::new(vertices.to_vec(), indices));
+        }
+    }
+
+    pub fn update_non_indexed_vertices(&mut self, vertices: &[V], topology: Topology) {
+        self.topology = topology;
+        self.upload(vertices, None);
+
+        if self.data.is_some() {
+            self.retain(MeshData::non_indexed(vertices.to_vec(), topology));
+        }
+    }
+
+    /// Replaces the retained data, if any
+    pub fn update_data(&mut self, data: &MeshData<V>) {
+        self.topology = data.topology;
+        self.upload(&data.vertices, data.indices.as_deref());
+
+        if self.data.is_some() {
+            self.retain(data.clone());
+        }
+    }
+
+    /// The data given to `from_data_retained`
+    pub fn get_data(&self) -> Option<&MeshData<V>> {
+        self.data.as_ref()
+    }
+
+    /// Detaches the retained data
+    pub fn take_data(&mut self) -> Option<MeshData<V>> {
+        self.bvh = None;
+        self.data.take()
+    }
+
+    /// The retained data, otherwise reads the vertices and indices back from the GPU
+    pub fn read_data(&self) -> MeshData<V> {
+        if let Some(data) = &self.data {
+            return data.clone();
+        }
+        V::assert_layout();
+
+        let mut vertices = Vec::<V>::with_capacity(self.total_vertices);
+        let mut indices = Vec::<u32>::with_capacity(self.total_indices);
+        unsafe {
+            gl::BindBuffer(gl::COPY_READ_BUFFER, self.vbo);
+            gl::GetBufferSubData(
+                gl::COPY_READ_BUFFER,
+                0,
+                (self.total_vertices * std::mem::size_of::<V>()) as isize,
+                vertices.as_mut_ptr() as *mut _,
+            );
+            vertices.set_len(self.total_vertices);
+
+            gl::BindBuffer(gl::COPY_READ_BUFFER, self.ebo);
+            match self.index_format {
+                Some(IndexFormat::U16) => {
+                    let mut short_indices = Vec::<u16>::with_capacity(self.total_indices);
+                    gl::GetBufferSubData(
+                        gl::COPY_READ_BUFFER,
+                        0,
+                        (self.total_indices * std::mem::size_of::<u16>()) as isize,
+                        short_indices.as_mut_ptr() as *mut _,
+                    );
+                    short_indices.set_len(self.total_indices);
+                    indices.extend(short_indices.into_iter().map(u32::from));
+                }
+                Some(IndexFormat::U32) => {
+                    gl::GetBufferSubData(
+                        gl::COPY_READ_BUFFER,
+                        0,
+                        (self.total_indices * std::mem::size_of::<u32>()) as isize,
+                        indices.as_mut_ptr() as *mut _,
+                    );
+                    indices.set_len(self.total_indices);
+                }
+                None => (),
+            }
+        }
+
+        MeshData {
+            vertices,
+            indices: self.index_format.map(|_| indices),
+            topology: self.topology,
+        }
+    }
+
+    fn retain(&mut self, data: MeshData<V>) {
+        if let Err(error) = data.validate() {
+            panic!("Invalid mesh data: {error}");
+        }
+
+        self.bvh = Some(Bvh::new(
+            &get_positions(&data.vertices),
+            &data.get_triangles(),
+        ));
+        self.data = Some(data);
+    }
+
+    /// Binds the vertex array and fills the buffers, growing them when needed
+    fn upload(&mut self, vertices: &[V], indices: Option<&[u32]>) {
+        V::assert_layout();
 
         unsafe {
             gl::BindVertexArray(self.vao);
-            gl::DrawElements(
-                gl::TRIANGLES,
-                (self.total_faces * 3) as i32,
-                gl::UNSIGNED_INT,
-                0 as *const std::ffi::c_void,
-            )
+
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
         }
+        upload_buffer(
+            gl::ARRAY_BUFFER,
+            vertices,
+            &mut self.vertex_buffer_size,
+            self.usage_type,
+        );
+
+        unsafe {
+            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.ebo);
+        }
+        debug_assert!(
+            indices.is_none_or(|indices| indices
+                .iter()
+                .all(|&index| (index as usize) < vertices.len())),
+            "An index is out of bounds for {} vertices",
+            vertices.len()
+        );
+        self.index_format =
+            indices.map(|indices| IndexFormat::for_indices(vertices.len(), indices));
+        match (indices, self.index_format) {
+            (Some(indices), Some(IndexFormat::U16)) => upload_buffer(
+                gl::ELEMENT_ARRAY_BUFFER,
+                &indices.iter().map(|&i| i as u16).collect::<Vec<u16>>(),
+                &mut self.index_buffer_size,
+                self.usage_type,
+            ),
+            (Some(indices), _) => upload_buffer(
+                gl::ELEMENT_ARRAY_BUFFER,
+                indices,
+                &mut self.index_buffer_size,
+                self.usage_type,
+            ),
+            (None, _) => (),
+        }
+
+        self.total_vertices = vertices.len();
+        self.total_indices = indices.map_or(0, |indices| indices.len());
+
+        let positions = get_positions(vertices);
+        self.aabb = Aabb::from_points(&positions);
+        self.bounding_sphere = BoundingSphere::from_points(&positions);
+    }
+
+    pub fn get_topology(&self) -> Topology {
+        self.topology
+    }
+
+    /// In local space
+    pub fn get_aabb(&self) -> Aabb {
+        self.aabb
+    }
+
+    /// In local space
+    pub fn get_bounding_sphere(&self) -> BoundingSphere {
+        self.bounding_sphere
+    }
+
+    /// `None` for non-indexed meshes
+    pub fn get_index_format(&self) -> Option<IndexFormat> {
+        self.index_format
     }
+}
 
+impl<V: MeshVertex> Mesh<V> {
+    /// In local space. Always misses unless the mesh keeps its data, see `from_data_retained`.
+    pub fn raycast(&self, ray: &Ray) -> Option<Hit> {
+        self.data
+            .as_ref()?
+            .raycast_with_bvh(ray, self.bvh.as_ref()?)
+    }
+}
+
+impl Mesh<Vertex> {
+    /// Generates normals with `NormalGeneration::default()` if the mesh has none
     pub fn from_tobj(obj: &tobj::Mesh, usage_type: UsageType) -> Self {
+        Self::from_tobj_with_normals(obj, usage_type, Default::default())
+    }
+
+    pub fn from_tobj_with_normals(
+        obj: &tobj::Mesh,
+        usage_type: UsageType,
+        normal_generation: NormalGeneration,
+    ) -> Self {
+        let (vertices, faces) = Self::vertices_from_tobj(obj, normal_generation);
+
+        Self::from_vertices(&vertices, &faces, usage_type)
+    }
+
+    fn vertices_from_tobj(
+        obj: &tobj::Mesh,
+        normal_generation: NormalGeneration,
+    ) -> (Vec<Vertex>, Vec<Vector3<u32>>) {
         let vertices = (0..(obj.positions.len() / 3))
             .map(|i| {
                 Vertex::new(
@@ -74,147 +534,64 @@ impl Mesh {
             })
             .collect::<Vec<Vector3<u32>>>();
 
-        Self::from_vertices(&vertices, &faces, usage_type)
-    }
-
-    pub fn from_vertices(
-        vertices: &[Vertex],
-        faces: &[Vector3<u32>],
-        usage_type: UsageType,
-    ) -> Self {
-        let mut mesh = Self {
-            vao: 0,
-            vbo: 0,
-            ebo: 0,
-            vertex_buffer_size: vertices.len(),
-            face_buffer_size: faces.len(),
-            total_faces: faces.len(),
-            usage_type,
-        };
-        unsafe {
-            gl::GenVertexArrays(1, &mut mesh.vao);
-            gl::GenBuffers(1, &mut mesh.vbo);
-            gl::GenBuffers(1, &mut mesh.ebo);
-
-            gl::BindVertexArray(mesh.vao);
-
-            gl::BindBuffer(gl::ARRAY_BUFFER, mesh.vbo);
-            gl::BufferData(
-                gl::ARRAY_BUFFER,
-                (vertices.len() * std::mem::size_of::<Vertex>()) as isize,
-                vertices.as_ptr() as *const gl::types::GLvoid,
-                usage_type as u32,
-            );
-
-            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, mesh.ebo);
-            gl::BufferData(
-                gl::ELEMENT_ARRAY_BUFFER,
-                (faces.len() * std::mem::size_of::<Vector3<u32>>()) as isize,
-                faces.as_ptr() as *const gl::types::GLvoid,
-                usage_type as u32,
-            );
-        }
-
-        let mut offset = 0;
-
-        for (i, length) in Vertex::lengths().iter().enumerate() {
-            unsafe {
-                gl::VertexAttribPointer(
-                    i as u32,
-                    *length as i32,
-                    gl::FLOAT,
-                    gl::FALSE,
-                    std::mem::size_of::<Vertex>() as i32,
-                    offset as *const gl::types::GLvoid,
-                );
-                gl::EnableVertexAttribArray(i as u32);
+        match normal_generation {
+            NormalGeneration::IfMissing { crease_angle } if obj.normals.is_empty() => {
+                normals::generate_normals(&vertices, &faces, crease_angle)
             }
-
-            offset += length * std::mem::size_of::<f32>();
-        }
-
-        mesh
-    }
-
-    pub fn update_vertices(&mut self, vertices: &[Vertex], faces: &[Vector3<u32>]) {
-        unsafe {
-            gl::BindVertexArray(self.vao);
-
-            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
-        }
-        if vertices.len() > self.vertex_buffer_size {
-            unsafe {
-                gl::BufferData(
-                    gl::ARRAY_BUFFER,
-                    (vertices.len() * std::mem::size_of::<Vertex>()) as isize,
-                    vertices.as_ptr() as *const gl::types::GLvoid,
-                    self.usage_type as u32,
-                );
-            }
-
-            self.vertex_buffer_size = vertices.len();
-        } else {
-            unsafe {
-                gl::BufferSubData(
-                    gl::ARRAY_BUFFER,
-                    0,
-                    (vertices.len() * std::mem::size_of::<Vertex>()) as isize,
-                    vertices.as_ptr() as *const gl::types::GLvoid,
-                )
+            NormalGeneration::Always { crease_angle } => {
+                normals::generate_normals(&vertices, &faces, crease_angle)
             }
+            _ => (vertices, faces),
         }
+    }
+}
 
-        unsafe {
-            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.ebo);
-        }
-        if faces.len() > self.face_buffer_size {
-            unsafe {
-                gl::BufferData(
-                    gl::ELEMENT_ARRAY_BUFFER,
-                    (faces.len() * std::mem::size_of::<Vector3<u32>>()) as isize,
-                    faces.as_ptr() as *const gl::types::GLvoid,
-                    self.usage_type as u32,
-                );
-            }
-
-            self.face_buffer_size = faces.len();
-        } else {
-            unsafe {
-                gl::BufferSubData(
-                    gl::ELEMENT_ARRAY_BUFFER,
-                    0,
-                    (faces.len() * std::mem::size_of::<Vector3<u32>>()) as isize,
-                    faces.as_ptr() as *const gl::types::GLvoid,
-                );
-            }
-        }
+impl Mesh<TangentVertex> {
+    /// Generates normals like `from_tobj` and MikkTSpace tangents for normal mapping
+    pub fn from_tobj_with_tangents(
+        obj: &tobj::Mesh,
+        usage_type: UsageType,
+        normal_generation: NormalGeneration,
+    ) -> Self {
+        let (vertices, faces) = Mesh::vertices_from_tobj(obj, normal_generation);
+        let (vertices, faces) = tangents::generate_tangents(&vertices, &faces);
 
-        self.total_faces = faces.len();
+        Self::from_vertices(&vertices, &faces, usage_type)
     }
 }
 
-impl Clone for Mesh {
+impl<V: VertexLayout> Clone for Mesh<V> {
     fn clone(&self) -> Self {
         let mut mesh = Self {
             vao: 0,
             vbo: 0,
             ebo: 0,
+            instance_vbo: 0,
             vertex_buffer_size: self.vertex_buffer_size,
-            face_buffer_size: self.face_buffer_size,
-            total_faces: self.total_faces,
+            index_buffer_size: self.index_buffer_size,
+            total_vertices: self.total_vertices,
+            total_indices: self.total_indices,
+            index_format: self.index_format,
+            topology: self.topology,
             usage_type: self.usage_type,
+            data: self.data.clone(),
+            bvh: self.bvh.clone(),
+            aabb: self.aabb,
+            bounding_sphere: self.bounding_sphere,
+            vertex_type: PhantomData,
         };
 
         unsafe {
             gl::GenVertexArrays(1, &mut mesh.vao);
             gl::GenBuffers(1, &mut mesh.vbo);
             gl::GenBuffers(1, &mut mesh.ebo);
+            gl::GenBuffers(1, &mut mesh.instance_vbo);
 
             gl::BindBuffer(gl::COPY_READ_BUFFER, self.vbo);
             gl::BindBuffer(gl::COPY_WRITE_BUFFER, mesh.vbo);
             gl::BufferData(
                 gl::COPY_WRITE_BUFFER,
-                (mesh.vertex_buffer_size * std::mem::size_of::<Vertex>()) as isize,
+                mesh.vertex_buffer_size as isize,
                 null(),
                 mesh.usage_type as u32,
             );
@@ -223,14 +600,14 @@ impl Clone for Mesh {
                 gl::COPY_WRITE_BUFFER,
                 0,
                 0,
-                (mesh.vertex_buffer_size * std::mem::size_of::<Vertex>()) as isize,
+                mesh.vertex_buffer_size as isize,
             );
 
             gl::BindBuffer(gl::COPY_READ_BUFFER, self.ebo);
             gl::BindBuffer(gl::COPY_WRITE_BUFFER, mesh.ebo);
             gl::BufferData(
                 gl::COPY_WRITE_BUFFER,
-                (mesh.face_buffer_size * std::mem::size_of::<Vector3<u32>>()) as isize,
+                mesh.index_buffer_size as isize,
                 null(),
                 mesh.usage_type as u32,
             );
@@ -239,7 +616,7 @@ impl Clone for Mesh {
                 gl::COPY_WRITE_BUFFER,
                 0,
                 0,
-                (mesh.face_buffer_size * std::mem::size_of::<Vector3<u32>>()) as isize,
+                mesh.index_buffer_size as isize,
             );
 
             gl::BindVertexArray(mesh.vao);
@@ -247,34 +624,127 @@ impl Clone for Mesh {
             gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, mesh.ebo);
         }
 
-        let mut offset = 0;
-
-        for (i, length) in Vertex::lengths().iter().enumerate() {
-            unsafe {
-                gl::VertexAttribPointer(
-                    i as u32,
-                    *length as i32,
-                    gl::FLOAT,
-                    gl::FALSE,
-                    std::mem::size_of::<Vertex>() as i32,
-                    offset as *const gl::types::GLvoid,
-                );
-                gl::EnableVertexAttribArray(i as u32);
-            }
-
-            offset += length * std::mem::size_of::<f32>();
-        }
+        set_attribute_pointers::<V>(0, 0);
 
         mesh
     }
 }
 
-impl Drop for Mesh {
+impl<V: VertexLayout> Drop for Mesh<V> {
     fn drop(&mut self) {
         unsafe {
             gl::DeleteBuffers(1, &self.vbo);
             gl::DeleteBuffers(1, &self.ebo);
+            gl::DeleteBuffers(1, &self.instance_vbo);
             gl::DeleteVertexArrays(1, &self.vao);
         }
     }
 }
+
+/// The vertex array and the array buffer must be bound
+fn set_attribute_pointers<T: VertexLayout>(first_location: u32, divisor: u32) {
+    T::assert_layout();
+    let stride = std::mem::size_of::<T>();
+
+    for (i, (attribute, offset)) in T::ATTRIBUTES.iter().zip(T::offsets()).enumerate() {
+        let location = first_location + i as u32;
+
+        unsafe {
+            if attribute.attribute_type.is_integer() && !attribute.normalized {
+                gl::VertexAttribIPointer(
+                    location,
+                    attribute.components as i32,
+                    attribute.attribute_type as u32,
+                    stride as i32,
+                    offset as *const gl::types::GLvoid,
+                );
+            } else {
+                gl::VertexAttribPointer(
+                    location,
+                    attribute.components as i32,
+                    attribute.attribute_type as u32,
+                    if attribute.normalized {
+                        gl::TRUE
+                    } else {
+                        gl::FALSE
+                    },
+                    stride as i32,
+                    offset as *const gl::types::GLvoid,
+                );
+            }
+            gl::EnableVertexAttribArray(location);
+            gl::VertexAttribDivisor(location, divisor);
+        }
+    }
+}
+
+/// The buffer must be bound, `capacity` is its size in bytes
+fn upload_buffer<T>(target: u32, data: &[T], capacity: &mut usize, usage_type: UsageType) {
+    let size = std::mem::size_of_val(data);
+
+    unsafe {
+        if size > *capacity {
+            gl::BufferData(
+                target,
+                size as isize,
+                data.as_ptr() as *const gl::types::GLvoid,
+                usage_type as u32,
+            );
+
+            *capacity = size;
+        } else {
+            gl::BufferSubData(
+                target,
+                0,
+                size as isize,
+                data.as_ptr() as *const gl::types::GLvoid,
+            );
+        }
+    }
+}
+
+/// The first attribute of every vertex, read as its position. Empty if it isn't made of floats.
+fn get_positions<V: VertexLayout>(vertices: &[V]) -> Vec<Vector3<f32>> {
+    let Some(attribute) = V::ATTRIBUTES
+        .first()
+        .filter(|attribute| attribute.attribute_type == AttributeType::F32)
+    else {
+        return Vec::new();
+    };
+    let components = (attribute.components as usize).min(3);
+
+    vertices
+        .iter()
+        .map(|vertex| {
+            // The first attribute is at the start of the vertex
+            let values =
+                unsafe { std::slice::from_raw_parts(vertex as *const V as *const f32, components) };
+            let mut position = Vector3::zeros();
+            position.as_mut_slice()[..components].copy_from_slice(values);
+
+            position
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_format_fits_every_index() {
+        assert_eq!(IndexFormat::for_indices(4, &[0, 1, 2, 3]), IndexFormat::U16);
+        assert_eq!(
+            IndexFormat::for_indices(u16::MAX as usize + 1, &[0, u16::MAX as u32]),
+            IndexFormat::U16
+        );
+        assert_eq!(
+            IndexFormat::for_indices(4, &[0, 1, 70_000]),
+            IndexFormat::U32
+        );
+        assert_eq!(
+            IndexFormat::for_indices(70_000, &[0, 1, 2]),
+            IndexFormat::U32
+        );
+    }
+}
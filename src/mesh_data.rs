@@ -0,0 +1,468 @@
+use crate::{
+    na::{Matrix3, Matrix4, Point3, Vector2, Vector3, Vector4},
+    Bvh, ColorVertex, Hit, Indices, Mesh, Ray, TangentVertex, Topology, UsageType, Vertex,
+    VertexLayout,
+};
+use std::{error::Error, fmt};
+
+/// Vertices `MeshData` can read and transform
+pub trait MeshVertex: VertexLayout {
+    fn get_position(&self) -> Vector3<f32>;
+    fn set_position(&mut self, position: Vector3<f32>);
+
+    fn get_normal(&self) -> Option<Vector3<f32>> {
+        None
+    }
+
+    /// Ignored by vertices without a normal
+    fn set_normal(&mut self, _normal: Vector3<f32>) {}
+
+    fn get_tex_coord(&self) -> Option<Vector2<f32>> {
+        None
+    }
+
+    /// Transforms the position and the normal, if any. `normal_matrix` is the inverse transpose of
+    /// the upper 3x3 of `matrix`.
+    fn transform(&mut self, matrix: &Matrix4<f32>, normal_matrix: &Matrix3<f32>) {
+        transform_position_and_normal(self, matrix, normal_matrix);
+    }
+}
+
+fn transform_position_and_normal<V: MeshVertex>(
+    vertex: &mut V,
+    matrix: &Matrix4<f32>,
+    normal_matrix: &Matrix3<f32>,
+) {
+    vertex.set_position(
+        matrix
+            .transform_point(&Point3::from(vertex.get_position()))
+            .coords,
+    );
+    if let Some(normal) = vertex.get_normal() {
+        vertex.set_normal(
+            (normal_matrix * normal)
+                .try_normalize(f32::EPSILON)
+                .unwrap_or_default(),
+        );
+    }
+}
+
+impl MeshVertex for Vertex {
+    fn get_position(&self) -> Vector3<f32> {
+        self.position
+    }
+
+    fn set_position(&mut self, position: Vector3<f32>) {
+        self.position = position;
+    }
+
+    fn get_normal(&self) -> Option<Vector3<f32>> {
+        Some(self.normal)
+    }
+
+    fn set_normal(&mut self, normal: Vector3<f32>) {
+        self.normal = normal;
+    }
+
+    fn get_tex_coord(&self) -> Option<Vector2<f32>> {
+        Some(self.tex_coord)
+    }
+}
+
+impl MeshVertex for ColorVertex {
+    fn get_position(&self) -> Vector3<f32> {
+        self.position
+    }
+
+    fn set_position(&mut self, position: Vector3<f32>) {
+        self.position = position;
+    }
+
+    fn get_normal(&self) -> Option<Vector3<f32>> {
+        Some(self.normal)
+    }
+
+    fn set_normal(&mut self, normal: Vector3<f32>) {
+        self.normal = normal;
+    }
+
+    fn get_tex_coord(&self) -> Option<Vector2<f32>> {
+        Some(self.tex_coord)
+    }
+}
+
+impl MeshVertex for TangentVertex {
+    fn get_position(&self) -> Vector3<f32> {
+        self.position
+    }
+
+    fn set_position(&mut self, position: Vector3<f32>) {
+        self.position = position;
+    }
+
+    fn get_normal(&self) -> Option<Vector3<f32>> {
+        Some(self.normal)
+    }
+
+    fn set_normal(&mut self, normal: Vector3<f32>) {
+        self.normal = normal;
+    }
+
+    fn get_tex_coord(&self) -> Option<Vector2<f32>> {
+        Some(self.tex_coord)
+    }
+
+    fn transform(&mut self, matrix: &Matrix4<f32>, normal_matrix: &Matrix3<f32>) {
+        transform_position_and_normal(self, matrix, normal_matrix);
+
+        let tangent = (matrix.fixed_view::<3, 3>(0, 0) * self.tangent.xyz())
+            .try_normalize(f32::EPSILON)
+            .unwrap_or_default();
+        // Mirroring flips the handedness of the tangent space
+        let handedness = if matrix.fixed_view::<3, 3>(0, 0).determinant() < 0.0 {
+            -self.tangent.w
+        } else {
+            self.tangent.w
+        };
+        self.tangent = Vector4::new(tangent.x, tangent.y, tangent.z, handedness);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MeshDataError {
+    IndexOutOfBounds {
+        index: u32,
+        vertex_count: usize,
+    },
+    /// The number of vertices or indices doesn't make up whole primitives
+    IncompletePrimitive {
+        topology: Topology,
+        count: usize,
+    },
+}
+
+impl fmt::Display for MeshDataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MeshDataError::IndexOutOfBounds {
+                index,
+                vertex_count,
+            } => write!(
+                f,
+                "Index {index} is out of bounds for {vertex_count} vertices"
+            ),
+            MeshDataError::IncompletePrimitive { topology, count } => {
+                write!(f, "{count} elements don't make up whole {topology:?}")
+            }
+        }
+    }
+}
+
+impl Error for MeshDataError {}
+
+/// A CPU copy of the data of a `Mesh`
+#[derive(Clone)]
+pub struct MeshData<V: VertexLayout = Vertex> {
+    pub vertices: Vec<V>,
+    /// `None` for non-indexed meshes
+    pub indices: Option<Vec<u32>>,
+    pub topology: Topology,
+}
+
+impl<V: VertexLayout> MeshData<V> {
+    pub fn new(vertices: Vec<V>, indices: Indices) -> Self {
+        Self {
+            vertices,
+            indices: Some(indices.as_flat().to_vec()),
+            topology: indices.get_topology(),
+        }
+    }
+
+    pub fn from_faces(vertices: Vec<V>, faces: &[Vector3<u32>]) -> Self {
+        Self::new(vertices, Indices::Triangles(faces))
+    }
+
+    pub fn non_indexed(vertices: Vec<V>, topology: Topology) -> Self {
+        Self {
+            vertices,
+            indices: None,
+            topology,
+        }
+    }
+
+    /// The number of vertices drawn, after indexing
+    pub fn get_element_count(&self) -> usize {
+        self.indices
+            .as_ref()
+            .map_or(self.vertices.len(), |indices| indices.len())
+    }
+
+    /// The vertex index of every element, in draw order
+    pub fn get_element_indices(&self) -> Vec<u32> {
+        match &self.indices {
+            Some(indices) => indices.clone(),
+            None => (0..self.vertices.len() as u32).collect(),
+        }
+    }
+
+    /// Counter-clockwise triangles, with strips and fans unrolled. Empty for points and lines.
+    pub fn get_triangles(&self) -> Vec<Vector3<u32>> {
+        let elements = self.get_element_indices();
+
+        match self.topology {
+            Topology::Triangles => elements
+                .chunks_exact(3)
+                .map(|triangle| Vector3::new(triangle[0], triangle[1], triangle[2]))
+                .collect(),
+            Topology::TriangleStrip => (0..elements.len().saturating_sub(2))
+                .map(|i| {
+                    if i % 2 == 0 {
+                        Vector3::new(elements[i], elements[i + 1], elements[i + 2])
+                    } else {
+                        Vector3::new(elements[i + 1], elements[i], elements[i + 2])
+                    }
+                })
+                .collect(),
+            Topology::TriangleFan => (1..elements.len().saturating_sub(1))
+                .map(|i| Vector3::new(elements[0], elements[i], elements[i + 1]))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Checks that every index is in bounds and that the elements make up whole primitives
+    pub fn validate(&self) -> Result<(), MeshDataError> {
+        if let Some(indices) = &self.indices {
+            if let Some(&index) = indices
+                .iter()
+                .find(|&&index| index as usize >= self.vertices.len())
+            {
+                return Err(MeshDataError::IndexOutOfBounds {
+                    index,
+                    vertex_count: self.vertices.len(),
+                });
+            }
+        }
+
+        let count = self.get_element_count();
+        let complete = match self.topology {
+            Topology::Points => true,
+            Topology::Lines => count.is_multiple_of(2),
+            Topology::LineStrip | Topology::LineLoop => count != 1,
+            Topology::Triangles => count.is_multiple_of(3),
+            Topology::TriangleStrip | Topology::TriangleFan => count == 0 || count >= 3,
+        };
+
+        if complete {
+            Ok(())
+        } else {
+            Err(MeshDataError::IncompletePrimitive {
+                topology: self.topology,
+                count,
+            })
+        }
+    }
+
+    /// Appends the vertices and primitives of `other`. Only lists of points, lines or triangles
+    /// can be merged, since joining strips, loops or fans would connect the two meshes.
+    pub fn merge(&mut self, other: &Self) {
+        assert_eq!(
+            self.topology, other.topology,
+            "Only meshes with the same topology can be merged"
+        );
+        assert!(
+            matches!(
+                self.topology,
+                Topology::Points | Topology::Lines | Topology::Triangles
+            ),
+            "Only point, line and triangle lists can be merged"
+        );
+
+        let offset = self.vertices.len() as u32;
+
+        if self.indices.is_some() || other.indices.is_some() {
+            let mut indices = self.get_element_indices();
+            indices.extend(other.get_element_indices().iter().map(|i| i + offset));
+            self.indices = Some(indices);
+        }
+        self.vertices.extend_from_slice(&other.vertices);
+    }
+
+    /// The indices in the container matching the topology, `None` for non-indexed meshes
+    pub fn get_indices(&self) -> Option<Indices<'_>> {
+        let indices = self.indices.as_deref()?;
+
+        Some(match self.topology {
+            Topology::Points => Indices::Points(indices),
+            Topology::Lines => Indices::Lines(unsafe {
+                std::slice::from_raw_parts(
+                    indices.as_ptr() as *const Vector2<u32>,
+                    indices.len() / 2,
+                )
+            }),
+            Topology::LineStrip => Indices::LineStrip(indices),
+            Topology::LineLoop => Indices::LineLoop(indices),
+            Topology::Triangles => Indices::Triangles(unsafe {
+                std::slice::from_raw_parts(
+                    indices.as_ptr() as *const Vector3<u32>,
+                    indices.len() / 3,
+                )
+            }),
+            Topology::TriangleStrip => Indices::TriangleStrip(indices),
+            Topology::TriangleFan => Indices::TriangleFan(indices),
+        })
+    }
+
+    /// Uploads a copy of the data, see `Mesh::from_data_retained` to keep it attached
+    pub fn to_mesh(&self, usage_type: UsageType) -> Mesh<V> {
+        Mesh::from_data(self, usage_type)
+    }
+}
+
+impl<V: MeshVertex> MeshData<V> {
+    /// Mirroring transforms reverse the winding of triangle lists to keep them front facing
+    pub fn transform(&mut self, matrix: &Matrix4<f32>) {
+        let linear = matrix.fixed_view::<3, 3>(0, 0).into_owned();
+        let normal_matrix = linear
+            .try_inverse()
+            .map(|inverse| inverse.transpose())
+            .unwrap_or(linear);
+
+        for vertex in &mut self.vertices {
+            vertex.transform(matrix, &normal_matrix);
+        }
+
+        if linear.determinant() < 0.0 && self.topology == Topology::Triangles {
+            let mut elements = self.get_element_indices();
+            for triangle in elements.chunks_exact_mut(3) {
+                triangle.swap(1, 2);
+            }
+            self.indices = Some(elements);
+        }
+    }
+
+    pub fn get_positions(&self) -> Vec<Vector3<f32>> {
+        self.vertices
+            .iter()
+            .map(|vertex| vertex.get_position())
+            .collect()
+    }
+
+    pub fn build_bvh(&self) -> Bvh {
+        Bvh::new(&self.get_positions(), &self.get_triangles())
+    }
+
+    /// Builds a BVH every call, meshes from `Mesh::from_data_retained` keep theirs
+    pub fn raycast(&self, ray: &Ray) -> Option<Hit> {
+        self.raycast_with_bvh(ray, &self.build_bvh())
+    }
+
+    /// `bvh` must be built from this data
+    pub fn raycast_with_bvh(&self, ray: &Ray, bvh: &Bvh) -> Option<Hit> {
+        let (triangle_index, distance, barycentric) = bvh.raycast(ray)?;
+        let triangle = bvh.get_triangles()[triangle_index];
+        let vertices = [
+            self.vertices[triangle.x as usize],
+            self.vertices[triangle.y as usize],
+            self.vertices[triangle.z as usize],
+        ];
+
+        let face_normal = || {
+            let [a, b, c] = vertices.map(|vertex| vertex.get_position());
+            (b - a).cross(&(c - a)).normalize()
+        };
+        let normal = match vertices.map(|vertex| vertex.get_normal()) {
+            [Some(a), Some(b), Some(c)] => {
+                (a * barycentric.x + b * barycentric.y + c * barycentric.z)
+                    .try_normalize(f32::EPSILON)
+                    .unwrap_or_else(face_normal)
+            }
+            _ => face_normal(),
+        };
+        let tex_coord = match vertices.map(|vertex| vertex.get_tex_coord()) {
+            [Some(a), Some(b), Some(c)] => {
+                Some(a * barycentric.x + b * barycentric.y + c * barycentric.z)
+            }
+            _ => None,
+        };
+
+        Some(Hit {
+            distance,
+            point: ray.get_point(distance),
+            triangle_index,
+            barycentric,
+            normal,
+            tex_coord,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vertex_types_transform_alike() {
+        // Non-uniform scale and a mirror along x
+        let matrix = Matrix4::new_translation(&Vector3::new(1.0, 2.0, 3.0))
+            * Matrix4::new_nonuniform_scaling(&Vector3::new(-2.0, 1.0, 1.0));
+        let normal_matrix = matrix
+            .fixed_view::<3, 3>(0, 0)
+            .try_inverse()
+            .unwrap()
+            .transpose();
+        let (position, normal) = (
+            Vector3::new(1.0, 1.0, 0.0),
+            Vector3::new(1.0, 1.0, 0.0).normalize(),
+        );
+
+        let mut vertex = Vertex::new(position, normal, Vector2::zeros());
+        let mut color_vertex =
+            ColorVertex::new(position, normal, Vector2::zeros(), Vector4::zeros());
+        let mut tangent_vertex = TangentVertex::new(
+            position,
+            normal,
+            Vector2::zeros(),
+            Vector4::new(0.0, 0.0, 1.0, 1.0),
+        );
+        vertex.transform(&matrix, &normal_matrix);
+        color_vertex.transform(&matrix, &normal_matrix);
+        tangent_vertex.transform(&matrix, &normal_matrix);
+
+        assert_eq!(vertex.position, Vector3::new(-1.0, 3.0, 3.0));
+        assert!((vertex.normal - Vector3::new(-1.0, 2.0, 0.0).normalize()).norm() < 1e-6);
+        for (other_position, other_normal) in [
+            (color_vertex.position, color_vertex.normal),
+            (tangent_vertex.position, tangent_vertex.normal),
+        ] {
+            assert_eq!(other_position, vertex.position);
+            assert_eq!(other_normal, vertex.normal);
+        }
+        assert_eq!(tangent_vertex.tangent, Vector4::new(0.0, 0.0, 1.0, -1.0));
+    }
+
+    #[test]
+    fn validate_reports_bad_data() {
+        let vertices = vec![Vertex::default(); 3];
+
+        let error = MeshData::from_faces(vertices.clone(), &[Vector3::new(0, 1, 3)])
+            .validate()
+            .unwrap_err();
+        assert_eq!(
+            error,
+            MeshDataError::IndexOutOfBounds {
+                index: 3,
+                vertex_count: 3
+            }
+        );
+        assert_eq!(error.to_string(), "Index 3 is out of bounds for 3 vertices");
+
+        let error = MeshData::non_indexed(vertices.clone(), Topology::Lines)
+            .validate()
+            .unwrap_err();
+        assert_eq!(error.to_string(), "3 elements don't make up whole Lines");
+        assert!(MeshData::non_indexed(vertices, Topology::Triangles)
+            .validate()
+            .is_ok());
+    }
+}
@@ -0,0 +1,562 @@
+use crate::{
+    na::{Vector2, Vector3},
+    AlphaMode, MeshData, MeshVertex, ObjMaterial, PbrMaterial, Topology, Vertex,
+};
+use serde_json::json;
+use std::{fmt::Write, io, path::Path};
+
+/// A mesh to export. `material` indexes the materials given to the exporter.
+pub struct ExportMesh<'a, V: MeshVertex = Vertex> {
+    pub name: &'a str,
+    pub data: &'a MeshData<V>,
+    pub material: Option<usize>,
+}
+
+impl<'a, V: MeshVertex> ExportMesh<'a, V> {
+    pub fn new(name: &'a str, data: &'a MeshData<V>) -> Self {
+        Self {
+            name,
+            data,
+            material: None,
+        }
+    }
+
+    pub fn with_material(mut self, material: usize) -> Self {
+        self.material = Some(material);
+        self
+    }
+}
+
+/// An OBJ file and the MTL file it references
+#[derive(Debug, Clone)]
+pub struct ObjExport {
+    pub obj: String,
+    pub mtl: String,
+    pub mtl_file_name: String,
+}
+
+impl ObjExport {
+    /// `texture_paths` are written for the texture indices of the materials, indices without a
+    /// path are left out. Meshes without a material are written first, since a material applies
+    /// to every object after it.
+    pub fn new<V: MeshVertex>(
+        meshes: &[ExportMesh<V>],
+        materials: &[ObjMaterial],
+        texture_paths: &[&str],
+        mtl_file_name: &str,
+    ) -> Self {
+        let mut obj = String::new();
+        let mut mtl = String::new();
+
+        if !materials.is_empty() {
+            writeln!(obj, "mtllib {mtl_file_name}").unwrap();
+        }
+
+        let get_material =
+            |mesh: &ExportMesh<V>| mesh.material.and_then(|index| materials.get(index));
+        let mut meshes = meshes.iter().collect::<Vec<_>>();
+        meshes.sort_by_key(|mesh| get_material(mesh).is_some());
+
+        // OBJ indices are 1-based and global to the file
+        let mut offset = 1;
+        for mesh in meshes {
+            let vertices = &mesh.data.vertices;
+            let has_tex_coords = vertices
+                .iter()
+                .any(|vertex| vertex.get_tex_coord().is_some());
+            let has_normals = vertices.iter().any(|vertex| vertex.get_normal().is_some());
+
+            writeln!(obj, "o {}", mesh.name).unwrap();
+            if let Some(material) = get_material(mesh) {
+                writeln!(obj, "usemtl {}", material.name).unwrap();
+            }
+
+            for vertex in vertices {
+                let position = vertex.get_position();
+                writeln!(obj, "v {} {} {}", position.x, position.y, position.z).unwrap();
+            }
+            if has_tex_coords {
+                for vertex in vertices {
+                    let tex_coord = vertex.get_tex_coord().unwrap_or_else(Vector2::zeros);
+                    writeln!(obj, "vt {} {}", tex_coord.x, tex_coord.y).unwrap();
+                }
+            }
+            if has_normals {
+                for vertex in vertices {
+                    let normal = vertex.get_normal().unwrap_or_else(Vector3::zeros);
+                    writeln!(obj, "vn {} {} {}", normal.x, normal.y, normal.z).unwrap();
+                }
+            }
+
+            let corner = |index: u32| {
+                let index = index as usize + offset;
+                match (has_tex_coords, has_normals) {
+                    (true, true) => format!("{index}/{index}/{index}"),
+                    (true, false) => format!("{index}/{index}"),
+                    (false, true) => format!("{index}//{index}"),
+                    (false, false) => index.to_string(),
+                }
+            };
+            let elements = mesh.data.get_element_indices();
+            match mesh.data.topology {
+                Topology::Points => {
+                    for &index in &elements {
+                        writeln!(obj, "p {}", index as usize + offset).unwrap();
+                    }
+                }
+                Topology::Lines | Topology::LineStrip | Topology::LineLoop => {
+                    let mut lines = match mesh.data.topology {
+                        Topology::Lines => elements
+                            .chunks_exact(2)
+                            .map(|line| [line[0], line[1]])
+                            .collect(),
+                        _ => elements
+                            .windows(2)
+                            .map(|line| [line[0], line[1]])
+                            .collect::<Vec<_>>(),
+                    };
+                    if let (Topology::LineLoop, [first, .., last]) =
+                        (mesh.data.topology, &elements[..])
+                    {
+                        lines.push([*last, *first]);
+                    }
+
+                    for line in lines {
+                        // Texture coordinates are the only per-vertex data lines can reference
+                        let [a, b] = line.map(|index| index as usize + offset);
+                        if has_tex_coords {
+                            writeln!(obj, "l {a}/{a} {b}/{b}").unwrap();
+                        } else {
+                            writeln!(obj, "l {a} {b}").unwrap();
+                        }
+                    }
+                }
+                Topology::Triangles | Topology::TriangleStrip | Topology::TriangleFan => {
+                    for triangle in mesh.data.get_triangles() {
+                        writeln!(
+                            obj,
+                            "f {} {} {}",
+                            corner(triangle.x),
+                            corner(triangle.y),
+                            corner(triangle.z)
+                        )
+                        .unwrap();
+                    }
+                }
+            }
+
+            offset += vertices.len();
+        }
+
+        let texture_path = |index: Option<usize>| index.and_then(|index| texture_paths.get(index));
+        for material in materials {
+            let diffuse = material.diffuse_color;
+            let specular = material.specular_color;
+
+            writeln!(mtl, "newmtl {}", material.name).unwrap();
+            writeln!(mtl, "Kd {} {} {}", diffuse.red, diffuse.green, diffuse.blue).unwrap();
+            writeln!(mtl, "d {}", diffuse.alpha).unwrap();
+            writeln!(
+                mtl,
+                "Ks {} {} {}",
+                specular.red, specular.green, specular.blue
+            )
+            .unwrap();
+            writeln!(mtl, "Ns {}", material.shininess).unwrap();
+            for (key, texture) in [
+                ("map_Kd", material.diffuse_texture),
+                ("map_Ks", material.specular_texture),
+                ("map_Bump", material.normal_texture),
+            ] {
+                if let Some(path) = texture_path(texture) {
+                    writeln!(mtl, "{key} {path}").unwrap();
+                }
+            }
+            writeln!(mtl).unwrap();
+        }
+
+        Self {
+            obj,
+            mtl,
+            mtl_file_name: mtl_file_name.to_string(),
+        }
+    }
+
+    /// Writes the MTL file next to the OBJ file, unless there are no materials
+    pub fn save(&self, obj_path: impl AsRef<Path>) -> io::Result<()> {
+        let obj_path = obj_path.as_ref();
+        std::fs::write(obj_path, &self.obj)?;
+
+        if !self.mtl.is_empty() {
+            let directory = obj_path.parent().unwrap_or(Path::new(""));
+            std::fs::write(directory.join(&self.mtl_file_name), &self.mtl)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes the meshes as a binary glTF (.glb) with one node per mesh. Material textures are left
+/// out since they have no image data on the CPU. Meshes without vertices or elements are skipped,
+/// glTF doesn't allow empty accessors.
+pub fn export_glb<V: MeshVertex>(meshes: &[ExportMesh<V>], materials: &[PbrMaterial]) -> Vec<u8> {
+    let meshes = meshes
+        .iter()
+        .filter(|mesh| !mesh.data.vertices.is_empty() && mesh.data.get_element_count() > 0)
+        .collect::<Vec<_>>();
+    let mut buffer = Vec::<u8>::new();
+    let mut buffer_views = Vec::new();
+    let mut accessors = Vec::new();
+    let mut gltf_meshes = Vec::new();
+
+    // Every view is 4-byte aligned since all components are 4 bytes
+    let mut push_view = |buffer: &mut Vec<u8>, data: Vec<u8>, target: u32| {
+        buffer_views.push(json!({
+            "buffer": 0,
+            "byteOffset": buffer.len(),
+            "byteLength": data.len(),
+            "target": target,
+        }));
+        buffer.extend(data);
+        buffer_views.len() - 1
+    };
+
+    for mesh in &meshes {
+        let vertices = &mesh.data.vertices;
+        let positions = mesh.data.get_positions();
+        let mut attributes = serde_json::Map::new();
+
+        let view = push_view(
+            &mut buffer,
+            to_bytes(
+                positions
+                    .iter()
+                    .flat_map(|position| position.iter().copied()),
+            ),
+            gl::ARRAY_BUFFER,
+        );
+        // Readers need the bounds of positions
+        let (min, max) = positions.iter().fold(
+            (Vector3::repeat(f32::MAX), Vector3::repeat(f32::MIN)),
+            |(min, max), position| (min.inf(position), max.sup(position)),
+        );
+        accessors.push(json!({
+            "bufferView": view,
+            "componentType": gl::FLOAT,
+            "count": vertices.len(),
+            "type": "VEC3",
+            "min": min.as_slice(),
+            "max": max.as_slice(),
+        }));
+        attributes.insert("POSITION".into(), json!(accessors.len() - 1));
+
+        if vertices.iter().any(|vertex| vertex.get_normal().is_some()) {
+            let view = push_view(
+                &mut buffer,
+                to_bytes(vertices.iter().flat_map(|vertex| {
+                    let normal = vertex.get_normal().unwrap_or_else(Vector3::zeros);
+                    [normal.x, normal.y, normal.z]
+                })),
+                gl::ARRAY_BUFFER,
+            );
+            accessors.push(json!({
+                "bufferView": view,
+                "componentType": gl::FLOAT,
+                "count": vertices.len(),
+                "type": "VEC3",
+            }));
+            attributes.insert("NORMAL".into(), json!(accessors.len() - 1));
+        }
+
+        if vertices
+            .iter()
+            .any(|vertex| vertex.get_tex_coord().is_some())
+        {
+            let view = push_view(
+                &mut buffer,
+                to_bytes(vertices.iter().flat_map(|vertex| {
+                    // glTF starts V at the top, the engine at the bottom like `GltfModel` loads it
+                    let tex_coord = vertex.get_tex_coord().unwrap_or_else(Vector2::zeros);
+                    [tex_coord.x, 1.0 - tex_coord.y]
+                })),
+                gl::ARRAY_BUFFER,
+            );
+            accessors.push(json!({
+                "bufferView": view,
+                "componentType": gl::FLOAT,
+                "count": vertices.len(),
+                "type": "VEC2",
+            }));
+            attributes.insert("TEXCOORD_0".into(), json!(accessors.len() - 1));
+        }
+
+        let mut primitive = json!({
+            "attributes": attributes,
+            "mode": match mesh.data.topology {
+                Topology::Points => 0,
+                Topology::Lines => 1,
+                Topology::LineLoop => 2,
+                Topology::LineStrip => 3,
+                Topology::Triangles => 4,
+                Topology::TriangleStrip => 5,
+                Topology::TriangleFan => 6,
+            },
+        });
+        if let Some(indices) = &mesh.data.indices {
+            let view = push_view(
+                &mut buffer,
+                indices
+                    .iter()
+                    .flat_map(|index| index.to_le_bytes())
+                    .collect(),
+                gl::ELEMENT_ARRAY_BUFFER,
+            );
+            accessors.push(json!({
+                "bufferView": view,
+                "componentType": gl::UNSIGNED_INT,
+                "count": indices.len(),
+                "type": "SCALAR",
+            }));
+            primitive["indices"] = json!(accessors.len() - 1);
+        }
+        if let Some(material) = mesh.material.filter(|&index| index < materials.len()) {
+            primitive["material"] = json!(material);
+        }
+
+        gltf_meshes.push(json!({
+            "name": mesh.name,
+            "primitives": [primitive],
+        }));
+    }
+
+    let gltf_materials = materials
+        .iter()
+        .map(|material| {
+            let base_color = material.base_color;
+            let emissive = material.emissive;
+            let mut gltf_material = json!({
+                "pbrMetallicRoughness": {
+                    "baseColorFactor": [
+                        base_color.red,
+                        base_color.green,
+                        base_color.blue,
+                        base_color.alpha,
+                    ],
+                    "metallicFactor": material.metallic,
+                    "roughnessFactor": material.roughness,
+                },
+                "emissiveFactor": [emissive.red, emissive.green, emissive.blue],
+                "alphaMode": match material.alpha_mode {
+                    AlphaMode::Opaque => "OPAQUE",
+                    AlphaMode::Mask => "MASK",
+                    AlphaMode::Blend => "BLEND",
+                },
+                "doubleSided": material.double_sided,
+            });
+            if let Some(name) = &material.name {
+                gltf_material["name"] = json!(name);
+            }
+            if material.alpha_mode == AlphaMode::Mask {
+                gltf_material["alphaCutoff"] = json!(material.alpha_cutoff);
+            }
+
+            gltf_material
+        })
+        .collect::<Vec<_>>();
+
+    let mut root = json!({
+        "asset": { "version": "2.0", "generator": "my_gl" },
+    });
+    // glTF arrays can't be empty, so a file without meshes has no scene or buffer
+    if !meshes.is_empty() {
+        root["scene"] = json!(0);
+        root["scenes"] = json!([{ "nodes": (0..meshes.len()).collect::<Vec<_>>() }]);
+        root["nodes"] = meshes
+            .iter()
+            .enumerate()
+            .map(|(i, mesh)| json!({ "name": mesh.name, "mesh": i }))
+            .collect();
+        root["meshes"] = json!(gltf_meshes);
+        root["accessors"] = json!(accessors);
+        root["bufferViews"] = json!(buffer_views);
+        root["buffers"] = json!([{ "byteLength": buffer.len() }]);
+    }
+    if !gltf_materials.is_empty() {
+        root["materials"] = json!(gltf_materials);
+    }
+
+    let mut json = serde_json::to_vec(&root).unwrap();
+    json.resize(json.len().next_multiple_of(4), b' ');
+    buffer.resize(buffer.len().next_multiple_of(4), 0);
+
+    // The binary chunk is optional and left out when there is no data
+    let binary_size = if buffer.is_empty() {
+        0
+    } else {
+        8 + buffer.len()
+    };
+    let mut glb = Vec::with_capacity(12 + 8 + json.len() + binary_size);
+    glb.extend(b"glTF");
+    glb.extend(2u32.to_le_bytes());
+    glb.extend(((12 + 8 + json.len() + binary_size) as u32).to_le_bytes());
+    glb.extend((json.len() as u32).to_le_bytes());
+    glb.extend(b"JSON");
+    glb.extend(json);
+    if !buffer.is_empty() {
+        glb.extend((buffer.len() as u32).to_le_bytes());
+        glb.extend(b"BIN\0");
+        glb.extend(buffer);
+    }
+
+    glb
+}
+
+fn to_bytes(values: impl Iterator<Item = f32>) -> Vec<u8> {
+    values.flat_map(f32::to_le_bytes).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use palette::LinSrgba;
+    use std::io::BufReader;
+
+    fn quad() -> MeshData {
+        MeshData::from_faces(
+            vec![
+                Vertex::new(Vector3::zeros(), Vector3::z(), Vector2::new(0.0, 0.0)),
+                Vertex::new(Vector3::x(), Vector3::z(), Vector2::new(1.0, 0.0)),
+                Vertex::new(
+                    Vector3::new(1.0, 1.0, 0.0),
+                    Vector3::z(),
+                    Vector2::new(1.0, 1.0),
+                ),
+                Vertex::new(Vector3::y(), Vector3::z(), Vector2::new(0.0, 0.25)),
+            ],
+            &[Vector3::new(0, 1, 2), Vector3::new(0, 2, 3)],
+        )
+    }
+
+    #[test]
+    fn obj_round_trips_through_tobj() {
+        let quad = quad();
+        let strip = MeshData::non_indexed(quad.vertices.clone(), Topology::TriangleStrip);
+        let material = ObjMaterial {
+            name: "red".to_string(),
+            diffuse_color: LinSrgba::new(1.0, 0.0, 0.0, 1.0),
+            diffuse_texture: Some(0),
+            ..Default::default()
+        };
+        let export = ObjExport::new(
+            &[
+                ExportMesh::new("quad", &quad).with_material(0),
+                ExportMesh::new("strip", &strip),
+            ],
+            &[material],
+            &["red.png"],
+            "quad.mtl",
+        );
+
+        let (models, materials) = tobj::load_obj_buf(
+            &mut BufReader::new(export.obj.as_bytes()),
+            &tobj::LoadOptions {
+                single_index: true,
+                ..Default::default()
+            },
+            |path| {
+                assert_eq!(path, Path::new("quad.mtl"));
+                tobj::load_mtl_buf(&mut BufReader::new(export.mtl.as_bytes()))
+            },
+        )
+        .unwrap();
+        let materials = materials.unwrap();
+
+        // The strip comes first so the quad's material doesn't carry over to it
+        assert_eq!(models.len(), 2);
+        assert_eq!(models[0].name, "strip");
+        assert_eq!(models[0].mesh.material_id, None);
+        // The strip is unrolled with every other triangle flipped to keep the winding
+        assert_eq!(models[0].mesh.indices, vec![0, 1, 2, 2, 1, 3]);
+        assert_eq!(models[1].name, "quad");
+        assert_eq!(models[1].mesh.material_id, Some(0));
+        assert_eq!(models[1].mesh.indices, vec![0, 1, 2, 0, 2, 3]);
+        assert_eq!(models[1].mesh.texcoords[6..], [0.0, 0.25]);
+
+        assert_eq!(materials[0].name, "red");
+        assert_eq!(materials[0].diffuse, Some([1.0, 0.0, 0.0]));
+        assert_eq!(materials[0].diffuse_texture.as_deref(), Some("red.png"));
+    }
+
+    #[test]
+    fn glb_can_be_read_back() {
+        let quad = quad();
+        let lines = MeshData::non_indexed(quad.vertices.clone(), Topology::LineLoop);
+        let glb = export_glb(
+            &[
+                ExportMesh::new("quad", &quad).with_material(0),
+                ExportMesh::new("lines", &lines),
+            ],
+            &[PbrMaterial::default()],
+        );
+        let gltf = gltf::Gltf::from_slice(&glb).unwrap();
+        let blob = gltf.blob.as_deref();
+
+        let meshes = gltf.meshes().collect::<Vec<_>>();
+        assert_eq!(meshes.len(), 2);
+        assert_eq!(gltf.nodes().len(), 2);
+
+        let primitive = meshes[0].primitives().next().unwrap();
+        let reader = primitive.reader(|_| blob);
+        assert_eq!(primitive.mode(), gltf::mesh::Mode::Triangles);
+        assert_eq!(primitive.material().index(), Some(0));
+        assert_eq!(
+            reader
+                .read_indices()
+                .unwrap()
+                .into_u32()
+                .collect::<Vec<_>>(),
+            vec![0, 1, 2, 0, 2, 3]
+        );
+        assert_eq!(primitive.bounding_box().max, [1.0, 1.0, 0.0]);
+        // Loaders flip V back to the bottom left convention
+        let tex_coords = reader
+            .read_tex_coords(0)
+            .unwrap()
+            .into_f32()
+            .map(|[u, v]| [u, 1.0 - v])
+            .collect::<Vec<_>>();
+        assert_eq!(tex_coords[3], [0.0, 0.25]);
+
+        let primitive = meshes[1].primitives().next().unwrap();
+        assert_eq!(primitive.mode(), gltf::mesh::Mode::LineLoop);
+        assert!(primitive.indices().is_none());
+        assert!(primitive.material().index().is_none());
+    }
+
+    #[test]
+    fn glb_skips_empty_meshes() {
+        let quad = quad();
+        let empty = MeshData::<Vertex>::non_indexed(Vec::new(), Topology::Triangles);
+        let no_elements = MeshData::from_faces(quad.vertices.clone(), &[]);
+
+        let glb = export_glb(
+            &[
+                ExportMesh::new("empty", &empty),
+                ExportMesh::new("quad", &quad),
+                ExportMesh::new("no elements", &no_elements),
+            ],
+            &[],
+        );
+        let gltf = gltf::Gltf::from_slice(&glb).unwrap();
+        let nodes = gltf.nodes().collect::<Vec<_>>();
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(nodes[0].mesh().unwrap().name(), Some("quad"));
+        assert!(gltf.accessors().all(|accessor| accessor.count() > 0));
+
+        let glb = export_glb(&[ExportMesh::new("empty", &empty)], &[]);
+        let gltf = gltf::Gltf::from_slice(&glb).unwrap();
+        assert_eq!(gltf.meshes().len(), 0);
+        assert_eq!(gltf.buffers().len(), 0);
+        assert!(gltf.blob.is_none());
+    }
+}
@@ -0,0 +1,999 @@
+use crate::{
+    na::{Vector2, Vector3, Vector4},
+    normals::{self, NormalGeneration},
+    resources::ModelError,
+    ColorVertex, Mesh, UsageType, Vertex,
+};
+
+/// Vertex and face data read from an STL or PLY file, like `Mesh::from_tobj` produces
+#[derive(Clone, Default)]
+pub struct ImportedMesh {
+    pub vertices: Vec<Vertex>,
+    pub faces: Vec<Vector3<u32>>,
+    /// One color per vertex, for PLY files with colors
+    pub colors: Option<Vec<Vector4<f32>>>,
+}
+
+impl ImportedMesh {
+    /// Reads binary and ASCII STL. Every facet gets its own three vertices with the facet normal,
+    /// computed from the winding if the file leaves it zero.
+    pub fn from_stl(data: &[u8]) -> Result<Self, ModelError> {
+        // Binary files may start with `solid` too, so the size decides
+        let binary_size = (data.len() >= 84).then(|| {
+            let triangle_count = u32::from_le_bytes([data[80], data[81], data[82], data[83]]);
+            84 + 50 * triangle_count as u64
+        });
+
+        let facets = if binary_size == Some(data.len() as u64) {
+            read_binary_stl(data)
+        } else if data.trim_ascii_start().starts_with(b"solid") {
+            read_ascii_stl(data)?
+        } else if let Some(binary_size) = binary_size {
+            return Err(invalid(
+                "STL",
+                &format!(
+                    "The binary file should be {binary_size} bytes for its triangle count, but it is {} bytes",
+                    data.len()
+                ),
+            ));
+        } else {
+            return Err(invalid("STL", "The file is too short to be binary STL"));
+        };
+
+        let mut mesh = Self::default();
+        for (normal, corners) in facets {
+            let normal = normal.try_normalize(f32::EPSILON).unwrap_or_else(|| {
+                (corners[1] - corners[0])
+                    .cross(&(corners[2] - corners[0]))
+                    .try_normalize(0.0)
+                    .unwrap_or_default()
+            });
+            let first = mesh.vertices.len() as u32;
+
+            mesh.vertices.extend(
+                corners
+                    .iter()
+                    .map(|corner| Vertex::new(*corner, normal, Vector2::zeros())),
+            );
+            mesh.faces.push(Vector3::new(first, first + 1, first + 2));
+        }
+
+        Ok(mesh)
+    }
+
+    /// Reads ASCII and binary PLY with positions and optional normals, texture coordinates and
+    /// colors. Polygons are triangulated as fans. Missing normals are smoothed per vertex so the
+    /// colors stay aligned.
+    pub fn from_ply(data: &[u8]) -> Result<Self, ModelError> {
+        let (header, body) = read_ply_header(data)?;
+        let mut reader = PlyReader {
+            format: header.format,
+            data: body,
+            header_size: data.len() - body.len(),
+            position: 0,
+            line: header.line_count,
+        };
+
+        let mut mesh = Self::default();
+        let mut has_normals = false;
+        for element in &header.elements {
+            match element.name.as_str() {
+                "vertex" => has_normals = read_ply_vertices(&mut reader, element, &mut mesh)?,
+                "face" => read_ply_faces(&mut reader, element, &mut mesh)?,
+                _ => {
+                    for _ in 0..element.count {
+                        reader.start_row();
+                        for property in &element.properties {
+                            reader.read_property(property)?;
+                        }
+                        reader.end_row()?;
+                    }
+                }
+            }
+        }
+
+        if let Some(face) = mesh
+            .faces
+            .iter()
+            .flatten()
+            .find(|&&index| index as usize >= mesh.vertices.len())
+        {
+            return Err(invalid(
+                "PLY",
+                &format!(
+                    "Face index {face} is out of bounds for {} vertices",
+                    mesh.vertices.len()
+                ),
+            ));
+        }
+        if !has_normals {
+            mesh.vertices = smooth_normals(&mesh.vertices, &mesh.faces);
+        }
+
+        Ok(mesh)
+    }
+
+    /// Only generates normals if `normal_generation` asks to, PLY and STL always have them
+    pub fn to_mesh(&self, usage_type: UsageType, normal_generation: NormalGeneration) -> Mesh {
+        match normal_generation {
+            NormalGeneration::Always { crease_angle } => {
+                let (vertices, faces) =
+                    normals::generate_normals(&self.vertices, &self.faces, crease_angle);
+                Mesh::from_vertices(&vertices, &faces, usage_type)
+            }
+            _ => Mesh::from_vertices(&self.vertices, &self.faces, usage_type),
+        }
+    }
+
+    /// White if the file has no colors
+    pub fn to_colored_mesh(&self, usage_type: UsageType) -> Mesh<ColorVertex> {
+        let vertices = self
+            .vertices
+            .iter()
+            .enumerate()
+            .map(|(i, vertex)| {
+                ColorVertex::new(
+                    vertex.position,
+                    vertex.normal,
+                    vertex.tex_coord,
+                    self.colors
+                        .as_ref()
+                        .map_or(Vector4::repeat(1.0), |colors| colors[i]),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        Mesh::from_vertices(&vertices, &self.faces, usage_type)
+    }
+}
+
+fn invalid(format: &str, message: &str) -> ModelError {
+    ModelError::Invalid(format!("{format}: {message}"))
+}
+
+type Facet = (Vector3<f32>, [Vector3<f32>; 3]);
+
+fn read_binary_stl(data: &[u8]) -> Vec<Facet> {
+    data[84..]
+        .chunks_exact(50)
+        .map(|facet| {
+            let read_vector = |offset: usize| {
+                Vector3::from_fn(|i, _| {
+                    let start = offset + i * 4;
+                    f32::from_le_bytes([
+                        facet[start],
+                        facet[start + 1],
+                        facet[start + 2],
+                        facet[start + 3],
+                    ])
+                })
+            };
+
+            (
+                read_vector(0),
+                [read_vector(12), read_vector(24), read_vector(36)],
+            )
+        })
+        .collect()
+}
+
+fn read_ascii_stl(data: &[u8]) -> Result<Vec<Facet>, ModelError> {
+    let text = std::str::from_utf8(data).map_err(|_| invalid("STL", "The file isn't UTF-8"))?;
+    let mut facets = Vec::new();
+    let mut normal = Vector3::zeros();
+    let mut corners = Vec::new();
+    let mut in_facet = false;
+
+    for (line_index, line) in text.lines().enumerate() {
+        let error = |message: &str| invalid("STL", &format!("line {}: {message}", line_index + 1));
+        let mut words = line.split_whitespace();
+        let parse_vector = |words: &mut std::str::SplitWhitespace| {
+            let mut vector = Vector3::zeros();
+            for component in vector.iter_mut() {
+                *component = words
+                    .next()
+                    .and_then(|word| word.parse::<f32>().ok())
+                    .ok_or_else(|| error("Expected three numbers"))?;
+            }
+            Ok::<_, ModelError>(vector)
+        };
+
+        match words.next() {
+            Some("facet") => {
+                if in_facet {
+                    return Err(error("A facet starts before the previous one ends"));
+                }
+                if words.next() != Some("normal") {
+                    return Err(error("Expected `facet normal`"));
+                }
+                normal = parse_vector(&mut words)?;
+                corners.clear();
+                in_facet = true;
+            }
+            Some("vertex") if in_facet => corners.push(parse_vector(&mut words)?),
+            Some("vertex") => return Err(error("A vertex is outside of a facet")),
+            Some("endfacet") => {
+                if !in_facet {
+                    return Err(error("`endfacet` without a facet"));
+                }
+                in_facet = false;
+                let [a, b, c] = corners[..] else {
+                    return Err(error(&format!(
+                        "A facet needs three vertices, it has {}",
+                        corners.len()
+                    )));
+                };
+                facets.push((normal, [a, b, c]));
+            }
+            Some("solid" | "outer" | "endloop" | "endsolid") | None => (),
+            Some(word) => return Err(error(&format!("Unexpected `{word}`"))),
+        }
+    }
+
+    if in_facet {
+        return Err(invalid("STL", "The file ends inside a facet"));
+    }
+
+    Ok(facets)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PlyFormat {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PlyType {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl PlyType {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "char" | "int8" => PlyType::I8,
+            "uchar" | "uint8" => PlyType::U8,
+            "short" | "int16" => PlyType::I16,
+            "ushort" | "uint16" => PlyType::U16,
+            "int" | "int32" => PlyType::I32,
+            "uint" | "uint32" => PlyType::U32,
+            "float" | "float32" => PlyType::F32,
+            "double" | "float64" => PlyType::F64,
+            _ => return None,
+        })
+    }
+
+    fn is_integer(&self) -> bool {
+        !matches!(self, PlyType::F32 | PlyType::F64)
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            PlyType::I8 | PlyType::U8 => 1,
+            PlyType::I16 | PlyType::U16 => 2,
+            PlyType::I32 | PlyType::U32 | PlyType::F32 => 4,
+            PlyType::F64 => 8,
+        }
+    }
+
+    /// The largest value of integer types, used to normalize colors
+    fn get_max(&self) -> f64 {
+        match self {
+            PlyType::I8 => i8::MAX as f64,
+            PlyType::U8 => u8::MAX as f64,
+            PlyType::I16 => i16::MAX as f64,
+            PlyType::U16 => u16::MAX as f64,
+            PlyType::I32 => i32::MAX as f64,
+            PlyType::U32 => u32::MAX as f64,
+            PlyType::F32 | PlyType::F64 => 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum PlyProperty {
+    Scalar {
+        name: String,
+        value_type: PlyType,
+    },
+    List {
+        name: String,
+        count_type: PlyType,
+        value_type: PlyType,
+    },
+}
+
+impl PlyProperty {
+    fn get_name(&self) -> &str {
+        match self {
+            PlyProperty::Scalar { name, .. } | PlyProperty::List { name, .. } => name,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct PlyElement {
+    name: String,
+    count: usize,
+    properties: Vec<PlyProperty>,
+}
+
+struct PlyHeader {
+    format: PlyFormat,
+    elements: Vec<PlyElement>,
+    line_count: usize,
+}
+
+fn read_ply_header(data: &[u8]) -> Result<(PlyHeader, &[u8]), ModelError> {
+    let mut format = None;
+    let mut elements: Vec<PlyElement> = Vec::new();
+    let mut position = 0;
+    let mut line_index = 0;
+
+    loop {
+        let line_end = data[position..]
+            .iter()
+            .position(|&byte| byte == b'\n')
+            .ok_or_else(|| invalid("PLY", "The header doesn't end with `end_header`"))?;
+        let line = std::str::from_utf8(&data[position..position + line_end])
+            .map_err(|_| invalid("PLY", "The header isn't UTF-8"))?
+            .trim();
+        position += line_end + 1;
+        line_index += 1;
+
+        let error = |message: &str| invalid("PLY", &format!("line {line_index}: {message}"));
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        let parse_type = |name: &str| {
+            PlyType::parse(name).ok_or_else(|| error(&format!("Unknown type `{name}`")))
+        };
+
+        if line_index == 1 {
+            if line != "ply" {
+                return Err(error("The file doesn't start with `ply`"));
+            }
+            continue;
+        }
+
+        match words[..] {
+            ["format", name, _version] => {
+                format = Some(match name {
+                    "ascii" => PlyFormat::Ascii,
+                    "binary_little_endian" => PlyFormat::BinaryLittleEndian,
+                    "binary_big_endian" => PlyFormat::BinaryBigEndian,
+                    _ => return Err(error(&format!("Unknown format `{name}`"))),
+                })
+            }
+            ["element", name, count] => elements.push(PlyElement {
+                name: name.to_string(),
+                count: count
+                    .parse()
+                    .map_err(|_| error(&format!("Invalid element count `{count}`")))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count_type, value_type, name] => elements
+                .last_mut()
+                .ok_or_else(|| error("A property comes before any element"))?
+                .properties
+                .push(PlyProperty::List {
+                    name: name.to_string(),
+                    count_type: parse_type(count_type)?,
+                    value_type: parse_type(value_type)?,
+                }),
+            ["property", value_type, name] => elements
+                .last_mut()
+                .ok_or_else(|| error("A property comes before any element"))?
+                .properties
+                .push(PlyProperty::Scalar {
+                    name: name.to_string(),
+                    value_type: parse_type(value_type)?,
+                }),
+            ["end_header"] => break,
+            ["comment" | "obj_info", ..] | [] => (),
+            _ => return Err(error(&format!("Unexpected `{line}`"))),
+        }
+    }
+
+    let format = format.ok_or_else(|| invalid("PLY", "The header has no format"))?;
+
+    Ok((
+        PlyHeader {
+            format,
+            elements,
+            line_count: line_index,
+        },
+        &data[position..],
+    ))
+}
+
+/// Reads the body of a PLY file, keeping track of the line or byte for errors
+struct PlyReader<'a> {
+    format: PlyFormat,
+    data: &'a [u8],
+    header_size: usize,
+    position: usize,
+    line: usize,
+}
+
+impl<'a> PlyReader<'a> {
+    fn error(&self, message: &str) -> ModelError {
+        match self.format {
+            PlyFormat::Ascii => invalid("PLY", &format!("line {}: {message}", self.line + 1)),
+            _ => invalid(
+                "PLY",
+                &format!("byte {}: {message}", self.header_size + self.position),
+            ),
+        }
+    }
+
+    /// ASCII rows are one line each
+    fn start_row(&mut self) {
+        if self.format == PlyFormat::Ascii {
+            while self
+                .data
+                .get(self.position)
+                .is_some_and(u8::is_ascii_whitespace)
+            {
+                if self.data[self.position] == b'\n' {
+                    self.line += 1;
+                }
+                self.position += 1;
+            }
+        }
+    }
+
+    /// ASCII rows can't have more values than the element has properties
+    fn end_row(&mut self) -> Result<(), ModelError> {
+        if self.format == PlyFormat::Ascii {
+            while self
+                .data
+                .get(self.position)
+                .is_some_and(|&byte| byte == b' ' || byte == b'\t' || byte == b'\r')
+            {
+                self.position += 1;
+            }
+            if self
+                .data
+                .get(self.position)
+                .is_some_and(|&byte| byte != b'\n')
+            {
+                return Err(self.error("The row has more values than the element has properties"));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read(&mut self, value_type: PlyType) -> Result<f64, ModelError> {
+        match self.format {
+            PlyFormat::Ascii => {
+                while self
+                    .data
+                    .get(self.position)
+                    .is_some_and(|&byte| byte == b' ' || byte == b'\t' || byte == b'\r')
+                {
+                    self.position += 1;
+                }
+                let start = self.position;
+                while self
+                    .data
+                    .get(self.position)
+                    .is_some_and(|byte| !byte.is_ascii_whitespace())
+                {
+                    self.position += 1;
+                }
+
+                let value = std::str::from_utf8(&self.data[start..self.position])
+                    .ok()
+                    .filter(|word| !word.is_empty())
+                    .and_then(|word| word.parse::<f64>().ok())
+                    .ok_or_else(|| self.error("Expected a number"))?;
+
+                if value_type.is_integer() && value.fract() != 0.0 {
+                    return Err(self.error(&format!("Expected an integer, found {value}")));
+                }
+
+                Ok(value)
+            }
+            PlyFormat::BinaryLittleEndian | PlyFormat::BinaryBigEndian => {
+                let bytes = self
+                    .data
+                    .get(self.position..self.position + value_type.size())
+                    .ok_or_else(|| self.error("The file ends before all elements are read"))?;
+                self.position += value_type.size();
+
+                let mut buffer = [0; 8];
+                buffer[..bytes.len()].copy_from_slice(bytes);
+                if self.format == PlyFormat::BinaryBigEndian {
+                    buffer[..bytes.len()].reverse();
+                }
+
+                Ok(match value_type {
+                    PlyType::I8 => buffer[0] as i8 as f64,
+                    PlyType::U8 => buffer[0] as f64,
+                    PlyType::I16 => i16::from_le_bytes([buffer[0], buffer[1]]) as f64,
+                    PlyType::U16 => u16::from_le_bytes([buffer[0], buffer[1]]) as f64,
+                    PlyType::I32 => {
+                        i32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as f64
+                    }
+                    PlyType::U32 => {
+                        u32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as f64
+                    }
+                    PlyType::F32 => {
+                        f32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as f64
+                    }
+                    PlyType::F64 => f64::from_le_bytes(buffer),
+                })
+            }
+        }
+    }
+
+    /// Scalars are returned as a single value
+    fn read_property(&mut self, property: &PlyProperty) -> Result<Vec<f64>, ModelError> {
+        match *property {
+            PlyProperty::Scalar { value_type, .. } => Ok(vec![self.read(value_type)?]),
+            PlyProperty::List {
+                count_type,
+                value_type,
+                ..
+            } => {
+                let count = self.read(count_type)?;
+                if count < 0.0 {
+                    return Err(self.error(&format!("Negative list length {count}")));
+                }
+
+                (0..count as usize).map(|_| self.read(value_type)).collect()
+            }
+        }
+    }
+}
+
+/// Returns whether the vertices have normals
+fn read_ply_vertices(
+    reader: &mut PlyReader,
+    element: &PlyElement,
+    mesh: &mut ImportedMesh,
+) -> Result<bool, ModelError> {
+    let find = |names: &[&str]| {
+        element
+            .properties
+            .iter()
+            .position(|property| names.contains(&property.get_name()))
+    };
+    let position = [find(&["x"]), find(&["y"]), find(&["z"])];
+    let normal = [find(&["nx"]), find(&["ny"]), find(&["nz"])];
+    let tex_coord = [
+        find(&["u", "s", "texture_u", "texture_s"]),
+        find(&["v", "t", "texture_v", "texture_t"]),
+    ];
+    let color = [
+        find(&["red", "r"]),
+        find(&["green", "g"]),
+        find(&["blue", "b"]),
+        find(&["alpha", "a"]),
+    ];
+
+    let [Some(x), Some(y), Some(z)] = position else {
+        return Err(invalid("PLY", "Vertices need x, y and z properties"));
+    };
+    let normal = match normal {
+        [Some(nx), Some(ny), Some(nz)] => Some([nx, ny, nz]),
+        _ => None,
+    };
+    let tex_coord = match tex_coord {
+        [Some(u), Some(v)] => Some([u, v]),
+        _ => None,
+    };
+    let has_color = color[..3].iter().all(Option::is_some);
+    let color_scale = |index: usize| match &element.properties[index] {
+        PlyProperty::Scalar { value_type, .. } => value_type.get_max(),
+        PlyProperty::List { .. } => 1.0,
+    };
+
+    let mut colors = Vec::new();
+    for _ in 0..element.count {
+        reader.start_row();
+        let values = element
+            .properties
+            .iter()
+            .map(|property| {
+                Ok(reader
+                    .read_property(property)?
+                    .first()
+                    .copied()
+                    .unwrap_or(0.0))
+            })
+            .collect::<Result<Vec<_>, ModelError>>()?;
+        reader.end_row()?;
+
+        let get = |index: usize| values[index] as f32;
+        mesh.vertices.push(Vertex::new(
+            Vector3::new(get(x), get(y), get(z)),
+            normal.map_or(Vector3::zeros(), |[nx, ny, nz]| {
+                Vector3::new(get(nx), get(ny), get(nz))
+            }),
+            tex_coord.map_or(Vector2::zeros(), |[u, v]| Vector2::new(get(u), get(v))),
+        ));
+
+        if has_color {
+            let channel = |index: Option<usize>| {
+                index.map_or(1.0, |index| (values[index] / color_scale(index)) as f32)
+            };
+            colors.push(Vector4::new(
+                channel(color[0]),
+                channel(color[1]),
+                channel(color[2]),
+                channel(color[3]),
+            ));
+        }
+    }
+
+    if has_color {
+        mesh.colors = Some(colors);
+    }
+
+    Ok(normal.is_some())
+}
+
+fn read_ply_faces(
+    reader: &mut PlyReader,
+    element: &PlyElement,
+    mesh: &mut ImportedMesh,
+) -> Result<(), ModelError> {
+    let indices_property = element
+        .properties
+        .iter()
+        .position(|property| {
+            matches!(property, PlyProperty::List { name, .. } if name == "vertex_indices" || name == "vertex_index")
+        })
+        .ok_or_else(|| invalid("PLY", "Faces need a vertex_indices list"))?;
+
+    for _ in 0..element.count {
+        reader.start_row();
+        for (i, property) in element.properties.iter().enumerate() {
+            let values = reader.read_property(property)?;
+
+            if i == indices_property {
+                if let Some(index) = values
+                    .iter()
+                    .find(|&&index| !(0.0..=u32::MAX as f64).contains(&index))
+                {
+                    return Err(reader.error(&format!("Invalid face index {index}")));
+                }
+
+                let polygon = values.iter().map(|&index| index as u32).collect::<Vec<_>>();
+                for corner in 1..polygon.len().saturating_sub(1) {
+                    mesh.faces.push(Vector3::new(
+                        polygon[0],
+                        polygon[corner],
+                        polygon[corner + 1],
+                    ));
+                }
+            }
+        }
+        reader.end_row()?;
+    }
+
+    Ok(())
+}
+
+/// Area weighted normals without splitting vertices
+fn smooth_normals(vertices: &[Vertex], faces: &[Vector3<u32>]) -> Vec<Vertex> {
+    let mut normals = vec![Vector3::zeros(); vertices.len()];
+
+    for face in faces {
+        let [a, b, c] = [face.x, face.y, face.z].map(|i| vertices[i as usize].position);
+        let normal = (b - a).cross(&(c - a));
+        for i in face.iter() {
+            normals[*i as usize] += normal;
+        }
+    }
+
+    vertices
+        .iter()
+        .zip(normals)
+        .map(|(vertex, normal)| {
+            Vertex::new(
+                vertex.position,
+                normal.try_normalize(0.0).unwrap_or_default(),
+                vertex.tex_coord,
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binary_stl(facets: &[Facet]) -> Vec<u8> {
+        let mut data = vec![0; 80];
+        data.extend((facets.len() as u32).to_le_bytes());
+        for (normal, corners) in facets {
+            for vector in std::iter::once(normal).chain(corners) {
+                data.extend(vector.iter().flat_map(|component| component.to_le_bytes()));
+            }
+            data.extend([0, 0]);
+        }
+        data
+    }
+
+    fn error_message(result: Result<ImportedMesh, ModelError>) -> String {
+        match result {
+            Err(ModelError::Invalid(message)) => message,
+            Err(error) => panic!("Unexpected error {error:?}"),
+            Ok(_) => panic!("The file should be rejected"),
+        }
+    }
+
+    const ASCII_PLY: &str = "ply
+format ascii 1.0
+comment a colored quad
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+end_header
+0 0 0 255 0 0
+1 0 0 0 255 0
+1 1 0 0 0 255
+0 1 0 255 255 255
+4 0 1 2 3
+";
+
+    fn ascii_ply(body: &str) -> Vec<u8> {
+        format!(
+            "ply
+format ascii 1.0
+element vertex 3
+property float x
+property float y
+property float z
+element face 1
+property list uchar int vertex_indices
+end_header
+{body}"
+        )
+        .into_bytes()
+    }
+
+    fn binary_ply(big_endian: bool) -> Vec<u8> {
+        let format = if big_endian {
+            "binary_big_endian"
+        } else {
+            "binary_little_endian"
+        };
+        let mut data = format!(
+            "ply
+format {format} 1.0
+element vertex 3
+property float x
+property float y
+property float z
+property float nx
+property float ny
+property float nz
+element face 1
+property list uchar uint vertex_indices
+end_header
+"
+        )
+        .into_bytes();
+
+        let positions = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0f32]];
+        for position in positions {
+            for component in position.into_iter().chain([0.0, 0.0, 1.0]) {
+                data.extend(if big_endian {
+                    component.to_be_bytes()
+                } else {
+                    component.to_le_bytes()
+                });
+            }
+        }
+        data.push(3);
+        for index in [0, 1, 2u32] {
+            data.extend(if big_endian {
+                index.to_be_bytes()
+            } else {
+                index.to_le_bytes()
+            });
+        }
+        data
+    }
+
+    #[test]
+    fn reads_ascii_stl() {
+        let data = b"solid triangle
+  facet normal 0 0 2
+    outer loop
+      vertex 0 0 0
+      vertex 1 0 0
+      vertex 0 1 0
+    endloop
+  endfacet
+endsolid triangle
+";
+        let mesh = ImportedMesh::from_stl(data).unwrap();
+
+        assert_eq!(mesh.faces, vec![Vector3::new(0, 1, 2)]);
+        assert_eq!(mesh.vertices[1].position, Vector3::new(1.0, 0.0, 0.0));
+        assert!(mesh
+            .vertices
+            .iter()
+            .all(|vertex| vertex.normal == Vector3::z()));
+    }
+
+    #[test]
+    fn reads_binary_stl_and_computes_missing_normals() {
+        let corners = [Vector3::zeros(), Vector3::x(), Vector3::y()];
+        // The header starts with `solid` like some exporters write it
+        let mut data = binary_stl(&[(Vector3::zeros(), corners), (-Vector3::z(), corners)]);
+        data[..5].copy_from_slice(b"solid");
+        let mesh = ImportedMesh::from_stl(&data).unwrap();
+
+        assert_eq!(mesh.vertices.len(), 6);
+        assert_eq!(
+            mesh.faces,
+            vec![Vector3::new(0, 1, 2), Vector3::new(3, 4, 5)]
+        );
+        assert_eq!(mesh.vertices[0].normal, Vector3::z());
+        assert_eq!(mesh.vertices[3].normal, -Vector3::z());
+    }
+
+    #[test]
+    fn rejects_invalid_stl() {
+        let corners = [Vector3::zeros(), Vector3::x(), Vector3::y()];
+        let mut truncated = binary_stl(&[(Vector3::z(), corners)]);
+        truncated.truncate(100);
+        assert!(error_message(ImportedMesh::from_stl(&truncated))
+            .contains("should be 134 bytes for its triangle count, but it is 100"));
+
+        let two_vertices = b"solid
+facet normal 0 0 1
+outer loop
+vertex 0 0 0
+vertex 1 0 0
+endloop
+endfacet
+endsolid
+";
+        assert_eq!(
+            error_message(ImportedMesh::from_stl(two_vertices)),
+            "STL: line 7: A facet needs three vertices, it has 2"
+        );
+
+        let bad_number = b"solid\nfacet normal 0 0 1\nvertex 0 zero 0\n";
+        assert_eq!(
+            error_message(ImportedMesh::from_stl(bad_number)),
+            "STL: line 3: Expected three numbers"
+        );
+
+        let unfinished = b"solid\nfacet normal 0 0 1\nouter loop\nvertex 0 0 0\n";
+        assert_eq!(
+            error_message(ImportedMesh::from_stl(unfinished)),
+            "STL: The file ends inside a facet"
+        );
+
+        assert!(ImportedMesh::from_stl(b"ply\nformat ascii 1.0\n").is_err());
+        assert!(ImportedMesh::from_stl(b"").is_err());
+    }
+
+    #[test]
+    fn reads_ascii_ply_with_colors() {
+        let mesh = ImportedMesh::from_ply(ASCII_PLY.as_bytes()).unwrap();
+
+        assert_eq!(mesh.vertices.len(), 4);
+        assert_eq!(
+            mesh.faces,
+            vec![Vector3::new(0, 1, 2), Vector3::new(0, 2, 3)]
+        );
+        assert_eq!(
+            mesh.colors.unwrap(),
+            vec![
+                Vector4::new(1.0, 0.0, 0.0, 1.0),
+                Vector4::new(0.0, 1.0, 0.0, 1.0),
+                Vector4::new(0.0, 0.0, 1.0, 1.0),
+                Vector4::new(1.0, 1.0, 1.0, 1.0),
+            ]
+        );
+        // The file has no normals, so they are smoothed from the faces
+        assert!(mesh
+            .vertices
+            .iter()
+            .all(|vertex| vertex.normal == Vector3::z()));
+    }
+
+    #[test]
+    fn reads_binary_ply_in_both_byte_orders() {
+        for big_endian in [false, true] {
+            let mesh = ImportedMesh::from_ply(&binary_ply(big_endian)).unwrap();
+
+            assert_eq!(mesh.faces, vec![Vector3::new(0, 1, 2)]);
+            assert_eq!(mesh.vertices[2].position, Vector3::new(0.0, 1.0, 0.0));
+            assert_eq!(mesh.vertices[2].normal, Vector3::z());
+            assert!(mesh.colors.is_none());
+        }
+    }
+
+    #[test]
+    fn rejects_truncated_ply() {
+        let data = binary_ply(false);
+        let header_size = data.len() - 3 * 24 - 13;
+        assert_eq!(
+            error_message(ImportedMesh::from_ply(&data[..data.len() - 2])),
+            format!(
+                "PLY: byte {}: The file ends before all elements are read",
+                header_size + 3 * 24 + 9
+            )
+        );
+
+        assert_eq!(
+            error_message(ImportedMesh::from_ply(&ascii_ply("0 0 0\n1 0 0\n"))),
+            "PLY: line 12: Expected a number"
+        );
+        assert_eq!(
+            error_message(ImportedMesh::from_ply(b"ply\nformat ascii 1.0\n")),
+            "PLY: The header doesn't end with `end_header`"
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_ply() {
+        let message = |body: &str| error_message(ImportedMesh::from_ply(&ascii_ply(body)));
+
+        assert_eq!(
+            message("0 0 0\n1 x 0\n0 1 0\n3 0 1 2\n"),
+            "PLY: line 11: Expected a number"
+        );
+        assert_eq!(
+            message("0 0 0\n1 0 0 5\n0 1 0\n3 0 1 2\n"),
+            "PLY: line 11: The row has more values than the element has properties"
+        );
+        assert_eq!(
+            message("0 0 0\n1 0 0\n0 1 0\n3 0 1.5 2\n"),
+            "PLY: line 13: Expected an integer, found 1.5"
+        );
+        assert_eq!(
+            message("0 0 0\n1 0 0\n0 1 0\n3 0 -1 2\n"),
+            "PLY: line 13: Invalid face index -1"
+        );
+        assert_eq!(
+            message("0 0 0\n1 0 0\n0 1 0\n3 0 1 3\n"),
+            "PLY: Face index 3 is out of bounds for 3 vertices"
+        );
+
+        let unknown_format = b"ply\nformat binary_middle_endian 1.0\nend_header\n";
+        assert_eq!(
+            error_message(ImportedMesh::from_ply(unknown_format)),
+            "PLY: line 2: Unknown format `binary_middle_endian`"
+        );
+        assert_eq!(
+            error_message(ImportedMesh::from_ply(b"solid\nendsolid\n")),
+            "PLY: line 1: The file doesn't start with `ply`"
+        );
+    }
+
+    #[test]
+    fn rejects_negative_list_lengths() {
+        let data = String::from_utf8(ascii_ply("0 0 0\n1 0 0\n0 1 0\n-1 0 1 2\n"))
+            .unwrap()
+            .replace("list uchar int", "list char int");
+
+        assert_eq!(
+            error_message(ImportedMesh::from_ply(data.as_bytes())),
+            "PLY: line 13: Negative list length -1"
+        );
+    }
+}
@@ -1,20 +1,26 @@
 use crate::{
-    na::Matrix4,
+    na::{Matrix4, Point3},
     shader_program::{ProgramValue, ShaderProgram},
-    Camera, GameObject, Mesh, Texture, Transform,
+    Aabb, BoundingSphere, Camera, GameObject, Hit, Mesh, MeshVertex, Ray, Texture, Transform,
+    Vertex, VertexLayout, DEFAULT_LAYER,
 };
 
 #[derive(Clone)]
-pub struct MeshObject<'a> {
-    pub mesh: &'a Mesh,
+pub struct MeshObject<'a, V: VertexLayout = Vertex> {
+    pub mesh: &'a Mesh<V>,
     pub textures: Vec<&'a Texture>,
     pub shader_program: &'a ShaderProgram,
     pub transform: Transform,
+    /// The world matrix of the parent, `transform` is relative to it
+    pub parent_matrix: Matrix4<f32>,
+    pub layer: u32,
+    /// Snaps the world position to the pixel grid of pixel perfect cameras
+    pub pixel_snap: bool,
 }
 
-impl<'a> MeshObject<'a> {
+impl<'a, V: VertexLayout> MeshObject<'a, V> {
     pub fn new(
-        mesh: &'a Mesh,
+        mesh: &'a Mesh<V>,
         textures: &[&'a Texture],
         shader_program: &'a ShaderProgram,
     ) -> Self {
@@ -23,20 +29,74 @@ impl<'a> MeshObject<'a> {
             textures: Vec::from(textures),
             shader_program,
             transform: Default::default(),
+            parent_matrix: Matrix4::identity(),
+            layer: DEFAULT_LAYER,
+            pixel_snap: false,
         };
 
         mesh_object
     }
+
+    pub fn with_layer(mut self, layer: u32) -> Self {
+        self.layer = layer;
+        self
+    }
+
+    pub fn with_pixel_snap(mut self, pixel_snap: bool) -> Self {
+        self.pixel_snap = pixel_snap;
+        self
+    }
+
+    pub fn get_world_aabb(&self) -> Aabb {
+        self.mesh.get_aabb().transform(&self.get_transform_matrix())
+    }
+
+    pub fn get_world_bounding_sphere(&self) -> BoundingSphere {
+        self.mesh
+            .get_bounding_sphere()
+            .transform(&self.get_transform_matrix())
+    }
 }
 
-impl<'a> GameObject for MeshObject<'a> {
+impl<'a, V: MeshVertex> MeshObject<'a, V> {
+    /// `ray` and the hit are in world space. The mesh has to keep its data, see
+    /// `Mesh::from_data_retained`.
+    pub fn raycast(&self, ray: &Ray) -> Option<Hit> {
+        let matrix = self.get_transform_matrix();
+        let inverse = matrix.try_inverse()?;
+        let local_ray = Ray::new(
+            inverse.transform_point(&Point3::from(ray.origin)).coords,
+            inverse.transform_vector(&ray.direction),
+        );
+
+        let hit = self.mesh.raycast(&local_ray)?;
+        let point = matrix.transform_point(&Point3::from(hit.point)).coords;
+        let normal_matrix = inverse.fixed_view::<3, 3>(0, 0).transpose();
+
+        Some(Hit {
+            distance: (point - ray.origin).norm(),
+            point,
+            normal: (normal_matrix * hit.normal).normalize(),
+            ..hit
+        })
+    }
+}
+
+impl<'a, V: VertexLayout> GameObject for MeshObject<'a, V> {
     fn draw(&mut self, camera: &Camera) {
+        let mut transform_matrix = self.get_transform_matrix();
+        if self.pixel_snap {
+            // Snaps the world position, a parent can move the local position off the grid
+            let position = camera.snap_to_pixel_grid(transform_matrix.column(3).xyz());
+            transform_matrix
+                .fixed_view_mut::<3, 1>(0, 3)
+                .copy_from(&position);
+        }
+
         self.shader_program.set_value(
             "transform",
             ProgramValue::Mat4(
-                camera.get_projection_matrix()
-                    * camera.get_transform_matrix()
-                    * self.get_transform_matrix(),
+                camera.get_projection_matrix() * camera.get_transform_matrix() * transform_matrix,
             ),
         );
 
@@ -48,6 +108,10 @@ impl<'a> GameObject for MeshObject<'a> {
     }
 
     fn get_transform_matrix(&self) -> Matrix4<f32> {
-        self.transform.to_matrix(false)
+        self.parent_matrix * self.transform.to_matrix()
+    }
+
+    fn get_layer(&self) -> u32 {
+        self.layer
     }
 }
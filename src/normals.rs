@@ -0,0 +1,282 @@
+//! Normal generation for meshes that don't come with normals
+
+use std::{collections::HashMap, f32::consts::PI};
+
+use crate::{na::Vector3, Vertex};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NormalGeneration {
+    /// Keeps the existing normals, zero if there are none
+    Never,
+    /// Generates normals only if the source has none
+    IfMissing {
+        crease_angle: f32,
+    },
+    Always {
+        crease_angle: f32,
+    },
+}
+
+impl Default for NormalGeneration {
+    fn default() -> Self {
+        NormalGeneration::IfMissing {
+            crease_angle: 60.0_f32.to_radians(),
+        }
+    }
+}
+
+/// Every face gets its own normal, vertices shared between faces are split
+pub fn generate_flat_normals(
+    vertices: &[Vertex],
+    faces: &[Vector3<u32>],
+) -> (Vec<Vertex>, Vec<Vector3<u32>>) {
+    generate_normals(vertices, faces, 0.0)
+}
+
+/// Smooths across every edge, vertices are only split if they have different normals at UV seams etc.
+pub fn generate_smooth_normals(
+    vertices: &[Vertex],
+    faces: &[Vector3<u32>],
+) -> (Vec<Vertex>, Vec<Vector3<u32>>) {
+    generate_normals(vertices, faces, PI)
+}
+
+/// Smooths across edges whose faces meet at less than `crease_angle` radians and keeps the rest sharp.
+/// Face normals are weighted by the angle of their corner, so the result doesn't depend on how faces are triangulated.
+/// Vertices at the same position are smoothed together even if they have different indices, and vertices
+/// that end up with several normals are split.
+pub fn generate_normals(
+    vertices: &[Vertex],
+    faces: &[Vector3<u32>],
+    crease_angle: f32,
+) -> (Vec<Vertex>, Vec<Vector3<u32>>) {
+    let face_normals = faces
+        .iter()
+        .map(|face| {
+            let [a, b, c] = corner_positions(vertices, face);
+            (b - a)
+                .cross(&(c - a))
+                .try_normalize(0.0)
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>();
+    let corner_angles = faces
+        .iter()
+        .map(|face| {
+            let [a, b, c] = corner_positions(vertices, face);
+            [
+                (b - a).angle(&(c - a)),
+                (c - b).angle(&(a - b)),
+                (a - c).angle(&(b - c)),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    // Every (face, corner) touching a position
+    let mut incidences = HashMap::<[u32; 3], Vec<(usize, usize)>>::new();
+    for (face_index, face) in faces.iter().enumerate() {
+        for corner in 0..3 {
+            incidences
+                .entry(position_key(&vertices[face[corner] as usize].position))
+                .or_default()
+                .push((face_index, corner));
+        }
+    }
+
+    let smooth = crease_angle > 0.0;
+    let min_dot = crease_angle.min(PI).cos();
+
+    let mut new_vertices = Vec::with_capacity(vertices.len());
+    let mut new_faces = Vec::with_capacity(faces.len());
+    // The normals each original vertex has been emitted with
+    let mut emitted = vec![Vec::<(Vector3<f32>, u32)>::new(); vertices.len()];
+
+    for (face_index, face) in faces.iter().enumerate() {
+        let face_normal = face_normals[face_index];
+        let mut new_face = Vector3::zeros();
+
+        for corner in 0..3 {
+            let vertex_index = face[corner] as usize;
+            let vertex = vertices[vertex_index];
+
+            let normal = if smooth {
+                incidences[&position_key(&vertex.position)]
+                    .iter()
+                    .filter(|(other_face, _)| {
+                        face_normals[*other_face].dot(&face_normal) >= min_dot
+                    })
+                    .map(|(other_face, other_corner)| {
+                        face_normals[*other_face] * corner_angles[*other_face][*other_corner]
+                    })
+                    .sum::<Vector3<f32>>()
+                    .try_normalize(0.0)
+                    .unwrap_or(face_normal)
+            } else {
+                face_normal
+            };
+
+            new_face[corner] = match emitted[vertex_index]
+                .iter()
+                .find(|(emitted_normal, _)| emitted_normal.dot(&normal) > 0.9999)
+            {
+                Some((_, index)) => *index,
+                None => {
+                    let index = new_vertices.len() as u32;
+                    new_vertices.push(Vertex { normal, ..vertex });
+                    emitted[vertex_index].push((normal, index));
+                    index
+                }
+            };
+        }
+
+        new_faces.push(new_face);
+    }
+
+    (new_vertices, new_faces)
+}
+
+fn corner_positions(vertices: &[Vertex], face: &Vector3<u32>) -> [Vector3<f32>; 3] {
+    [
+        vertices[face.x as usize].position,
+        vertices[face.y as usize].position,
+        vertices[face.z as usize].position,
+    ]
+}
+
+fn position_key(position: &Vector3<f32>) -> [u32; 3] {
+    // Adding 0 turns -0 into 0 so they hash the same
+    [
+        (position.x + 0.0).to_bits(),
+        (position.y + 0.0).to_bits(),
+        (position.z + 0.0).to_bits(),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::na::Vector2;
+
+    fn vertex(x: f32, y: f32, z: f32) -> Vertex {
+        Vertex::new(Vector3::new(x, y, z), Vector3::zeros(), Vector2::zeros())
+    }
+
+    /// A unit cube with one vertex per corner
+    fn shared_cube() -> (Vec<Vertex>, Vec<Vector3<u32>>) {
+        let vertices = (0..8)
+            .map(|i| vertex((i & 1) as f32, ((i >> 1) & 1) as f32, ((i >> 2) & 1) as f32))
+            .collect();
+        let faces = [
+            [0, 2, 3, 1],
+            [4, 5, 7, 6],
+            [0, 1, 5, 4],
+            [2, 6, 7, 3],
+            [0, 4, 6, 2],
+            [1, 3, 7, 5],
+        ]
+        .iter()
+        .flat_map(|&[a, b, c, d]| [Vector3::new(a, b, c), Vector3::new(a, c, d)])
+        .collect();
+
+        (vertices, faces)
+    }
+
+    /// Two triangles sharing the edge along z, the second one rotated by `fold` radians around it
+    fn folded_pair(fold: f32) -> (Vec<Vertex>, Vec<Vector3<u32>>) {
+        let (sin, cos) = fold.sin_cos();
+        let vertices = vec![
+            vertex(0.0, 0.0, 0.0),
+            vertex(0.0, 0.0, 1.0),
+            vertex(1.0, 0.0, 0.0),
+            vertex(-cos, sin, 0.0),
+        ];
+        let faces = vec![Vector3::new(0, 1, 2), Vector3::new(0, 3, 1)];
+
+        (vertices, faces)
+    }
+
+    #[test]
+    fn flat_cube_splits_every_corner_into_three() {
+        let (vertices, faces) = shared_cube();
+
+        for (new_vertices, new_faces) in [
+            generate_flat_normals(&vertices, &faces),
+            generate_normals(&vertices, &faces, 60.0_f32.to_radians()),
+        ] {
+            assert_eq!(new_vertices.len(), 24);
+            assert_eq!(new_faces.len(), 12);
+
+            for face in &new_faces {
+                let [a, b, c] = corner_positions(&new_vertices, face);
+                let face_normal = (b - a).cross(&(c - a)).normalize();
+                // Every normal points out of the cube along an axis
+                let center = (a + b + c) / 3.0 - Vector3::repeat(0.5);
+                assert_eq!(face_normal.abs().sum(), 1.0);
+                assert!(face_normal.dot(&center) > 0.0);
+                for index in face.iter() {
+                    assert_eq!(new_vertices[*index as usize].normal, face_normal);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn smooth_cube_keeps_the_corners_shared() {
+        let (vertices, faces) = shared_cube();
+        let (new_vertices, new_faces) = generate_smooth_normals(&vertices, &faces);
+
+        assert_eq!(new_vertices.len(), 8);
+        for (face, new_face) in faces.iter().zip(&new_faces) {
+            assert_eq!(
+                corner_positions(&vertices, face),
+                corner_positions(&new_vertices, new_face)
+            );
+        }
+        for vertex in &new_vertices {
+            // Angle weighting makes the corner normals point along the diagonals
+            let diagonal = (vertex.position - Vector3::repeat(0.5)).normalize();
+            assert!((vertex.normal - diagonal).norm() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn edges_are_smoothed_below_the_crease_angle() {
+        let crease_angle = 60.0_f32.to_radians();
+
+        let (vertices, faces) = folded_pair(30.0_f32.to_radians());
+        let (new_vertices, _) = generate_normals(&vertices, &faces, crease_angle);
+        assert_eq!(new_vertices.len(), 4);
+        // Both faces have a right angle at the first vertex, so they weigh the same
+        let half_fold = 15.0_f32.to_radians();
+        let bisector = Vector3::new(half_fold.sin(), half_fold.cos(), 0.0);
+        assert!((new_vertices[0].normal - bisector).norm() < 1e-5);
+        assert!((new_vertices[1].normal - bisector).norm() < 1e-5);
+
+        let (vertices, faces) = folded_pair(90.0_f32.to_radians());
+        let (new_vertices, new_faces) = generate_normals(&vertices, &faces, crease_angle);
+        assert_eq!(new_vertices.len(), 6);
+        assert_eq!(new_vertices[new_faces[0].x as usize].normal, Vector3::y());
+        assert!((new_vertices[new_faces[1].x as usize].normal - Vector3::x()).norm() < 1e-5);
+    }
+
+    #[test]
+    fn degenerate_triangles_dont_produce_nan() {
+        let (mut vertices, mut faces) = folded_pair(30.0_f32.to_radians());
+        // A collinear and a zero size triangle sharing vertices with the others
+        vertices.push(vertex(2.0, 0.0, 0.0));
+        faces.push(Vector3::new(0, 2, 4));
+        faces.push(Vector3::new(1, 1, 1));
+
+        for crease_angle in [0.0, 60.0_f32.to_radians(), PI] {
+            let (new_vertices, new_faces) = generate_normals(&vertices, &faces, crease_angle);
+
+            assert_eq!(new_faces.len(), 4);
+            assert!(new_vertices
+                .iter()
+                .all(|vertex| vertex.normal.iter().all(|component| component.is_finite())));
+            // The valid faces aren't affected by the degenerate ones
+            assert!(new_vertices[new_faces[0].z as usize].normal.y > 0.0);
+        }
+    }
+}
@@ -0,0 +1,248 @@
+use crate::{
+    resources::{load_resource, ModelError, ResourceProvider},
+    FilterMode, Mesh, Texture, UsageType, WrapMode,
+};
+use palette::{LinSrgb, LinSrgba};
+use std::{cell::RefCell, collections::HashMap, io::BufReader, path::Path};
+
+/// The MTL properties of a group. Textures are indices into `ObjModel::textures`.
+#[derive(Debug, Clone)]
+pub struct ObjMaterial {
+    pub name: String,
+    /// The alpha is the dissolve
+    pub diffuse_color: LinSrgba,
+    pub specular_color: LinSrgb,
+    pub shininess: f32,
+    pub diffuse_texture: Option<usize>,
+    pub specular_texture: Option<usize>,
+    pub normal_texture: Option<usize>,
+}
+
+impl Default for ObjMaterial {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            diffuse_color: LinSrgba::new(1.0, 1.0, 1.0, 1.0),
+            specular_color: LinSrgb::new(0.0, 0.0, 0.0),
+            shininess: 0.0,
+            diffuse_texture: None,
+            specular_texture: None,
+            normal_texture: None,
+        }
+    }
+}
+
+/// Every mesh that uses the same material
+pub struct MaterialGroup {
+    pub material: ObjMaterial,
+    pub meshes: Vec<Mesh>,
+}
+
+/// How `ObjModel::load_with_options` creates meshes and textures. MTL files have no sampler
+/// settings, so every texture gets these.
+#[derive(Clone, Copy)]
+pub struct ObjLoadOptions {
+    pub usage_type: UsageType,
+    pub wrap_mode: WrapMode,
+    pub min_filter_mode: FilterMode,
+    pub mag_filter_mode: FilterMode,
+}
+
+impl ObjLoadOptions {
+    /// Repeating trilinear textures, the glTF defaults for textures without a sampler
+    pub fn new(usage_type: UsageType) -> Self {
+        Self {
+            usage_type,
+            wrap_mode: WrapMode::Repeat,
+            min_filter_mode: FilterMode::LinearMipMapLinear,
+            mag_filter_mode: FilterMode::Linear,
+        }
+    }
+
+    pub fn with_wrap_mode(mut self, wrap_mode: WrapMode) -> Self {
+        self.wrap_mode = wrap_mode;
+        self
+    }
+
+    pub fn with_filter_modes(
+        mut self,
+        min_filter_mode: FilterMode,
+        mag_filter_mode: FilterMode,
+    ) -> Self {
+        self.min_filter_mode = min_filter_mode;
+        self.mag_filter_mode = mag_filter_mode;
+        self
+    }
+}
+
+/// Texture coordinates start at the bottom left of the images, like `primitives` and `GltfModel`
+pub struct ObjModel {
+    /// Meshes without a material share a group with `ObjMaterial::default()`, which comes last
+    pub groups: Vec<MaterialGroup>,
+    /// Every texture referenced by the materials, loaded once per path
+    pub textures: Vec<Texture>,
+    /// MTL files that couldn't be loaded. Groups using their materials get the default material.
+    pub missing_materials: Vec<ModelError>,
+}
+
+impl ObjModel {
+    /// MTL files are resolved relative to the OBJ file, textures relative to their MTL file.
+    /// Missing MTL files are collected in `missing_materials`, invalid ones fail the load.
+    pub fn load(
+        path: impl AsRef<Path>,
+        resources: &impl ResourceProvider,
+        usage_type: UsageType,
+    ) -> Result<Self, ModelError> {
+        Self::load_with_options(path, resources, ObjLoadOptions::new(usage_type))
+    }
+
+    pub fn load_with_options(
+        path: impl AsRef<Path>,
+        resources: &impl ResourceProvider,
+        options: ObjLoadOptions,
+    ) -> Result<Self, ModelError> {
+        let path = path.as_ref();
+        let obj_directory = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        let obj = load_resource(resources, path)?;
+
+        // The loader can't borrow mutably. Remembers where every material came from to resolve
+        // its textures.
+        let material_directories = RefCell::new(Vec::new());
+        let missing_materials = RefCell::new(Vec::new());
+        let (models, materials) = tobj::load_obj_buf(
+            &mut BufReader::new(obj.as_slice()),
+            &tobj::GPU_LOAD_OPTIONS,
+            |mtl_path| {
+                let mtl_path = obj_directory.join(mtl_path);
+                let mtl = match load_resource(resources, &mtl_path) {
+                    Ok(mtl) => mtl,
+                    Err(error) => {
+                        missing_materials.borrow_mut().push(error);
+                        return Ok(Default::default());
+                    }
+                };
+                let result = tobj::load_mtl_buf(&mut BufReader::new(mtl.as_slice()))?;
+
+                let mtl_directory = mtl_path.parent().unwrap_or(Path::new("")).to_path_buf();
+                let mut material_directories = material_directories.borrow_mut();
+                let material_count = material_directories.len() + result.0.len();
+                material_directories.resize(material_count, mtl_directory);
+
+                Ok(result)
+            },
+        )
+        .map_err(ModelError::Obj)?;
+        // Only MTL files that exist can fail here
+        let materials = materials.map_err(ModelError::Obj)?;
+
+        let mut textures = Vec::new();
+        let mut texture_indices = HashMap::new();
+        let mut load_texture = |directory: &Path, texture_path: &Option<String>| {
+            let Some(texture_path) = texture_path else {
+                return Ok(None);
+            };
+            let texture_path = directory.join(texture_path);
+
+            if let Some(&index) = texture_indices.get(&texture_path) {
+                return Ok(Some(index));
+            }
+
+            let image = image::load_from_memory(&load_resource(resources, &texture_path)?)
+                .map_err(|error| ModelError::Image(texture_path.clone(), error))?;
+            // OBJ texture coordinates start at the bottom left, like OpenGL's
+            textures.push(Texture::from_image(
+                image.flipv(),
+                options.wrap_mode,
+                options.min_filter_mode,
+                options.mag_filter_mode,
+            ));
+            texture_indices.insert(texture_path, textures.len() - 1);
+
+            Ok(Some(textures.len() - 1))
+        };
+
+        let mut groups = materials
+            .iter()
+            .zip(material_directories.into_inner())
+            .map(|(material, directory)| {
+                let diffuse = material.diffuse.unwrap_or([1.0; 3]);
+                let specular = material.specular.unwrap_or([0.0; 3]);
+
+                Ok(MaterialGroup {
+                    material: ObjMaterial {
+                        name: material.name.clone(),
+                        diffuse_color: LinSrgba::new(
+                            diffuse[0],
+                            diffuse[1],
+                            diffuse[2],
+                            material.dissolve.unwrap_or(1.0),
+                        ),
+                        specular_color: LinSrgb::new(specular[0], specular[1], specular[2]),
+                        shininess: material.shininess.unwrap_or(0.0),
+                        diffuse_texture: load_texture(&directory, &material.diffuse_texture)?,
+                        specular_texture: load_texture(&directory, &material.specular_texture)?,
+                        normal_texture: load_texture(&directory, &material.normal_texture)?,
+                    },
+                    meshes: Vec::new(),
+                })
+            })
+            .collect::<Result<Vec<_>, ModelError>>()?;
+
+        let mut default_group = MaterialGroup {
+            material: Default::default(),
+            meshes: Vec::new(),
+        };
+        // tobj also returns objects without faces, which have nothing to draw
+        for model in models.iter().filter(|model| !model.mesh.indices.is_empty()) {
+            let mesh = Mesh::from_tobj(&model.mesh, options.usage_type);
+
+            match model.mesh.material_id.and_then(|id| groups.get_mut(id)) {
+                Some(group) => group.meshes.push(mesh),
+                None => default_group.meshes.push(mesh),
+            }
+        }
+        if !default_group.meshes.is_empty() {
+            groups.push(default_group);
+        }
+
+        Ok(Self {
+            groups,
+            textures,
+            missing_materials: missing_materials.into_inner(),
+        })
+    }
+
+    pub fn get_texture(&self, index: Option<usize>) -> Option<&Texture> {
+        self.textures.get(index?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_mtl_files_use_the_default_material() {
+        let resources = HashMap::from([("model.obj", "mtllib missing.mtl\n")]);
+        let model = ObjModel::load("model.obj", &resources, UsageType::Static).unwrap();
+
+        assert!(model.groups.is_empty());
+        assert!(matches!(
+            &model.missing_materials[..],
+            [ModelError::Resource(path, _)] if path == Path::new("missing.mtl")
+        ));
+    }
+
+    #[test]
+    fn invalid_mtl_files_fail_the_load() {
+        let resources = HashMap::from([
+            ("model.obj", "mtllib broken.mtl\n"),
+            ("broken.mtl", "newmtl broken\nKd one two three\n"),
+        ]);
+
+        assert!(matches!(
+            ObjModel::load("model.obj", &resources, UsageType::Static),
+            Err(ModelError::Obj(_))
+        ));
+    }
+}
@@ -0,0 +1,567 @@
+//! Procedural meshes for `Mesh::from_vertices`. Front faces wind counter-clockwise and face outwards.
+
+use std::{collections::HashMap, f32::consts::PI};
+
+use crate::{
+    na::{Vector2, Vector3},
+    Vertex,
+};
+
+/// An axis aligned box centered on the origin
+pub fn cube(size: Vector3<f32>) -> (Vec<Vertex>, Vec<Vector3<u32>>) {
+    let half_size = size / 2.0;
+    let mut vertices = Vec::with_capacity(24);
+    let mut faces = Vec::with_capacity(12);
+
+    for (normal, u, v) in [
+        (Vector3::x(), -Vector3::z(), Vector3::y()),
+        (-Vector3::x(), Vector3::z(), Vector3::y()),
+        (Vector3::y(), Vector3::x(), -Vector3::z()),
+        (-Vector3::y(), Vector3::x(), Vector3::z()),
+        (Vector3::z(), Vector3::x(), Vector3::y()),
+        (-Vector3::z(), -Vector3::x(), Vector3::y()),
+    ] {
+        push_grid(
+            &mut vertices,
+            &mut faces,
+            normal.component_mul(&half_size),
+            u.component_mul(&half_size),
+            v.component_mul(&half_size),
+            normal,
+            Vector2::new(1, 1),
+        );
+    }
+
+    (vertices, faces)
+}
+
+/// A grid on the xz plane facing +y, centered on the origin
+pub fn plane(size: Vector2<f32>, subdivisions: Vector2<u32>) -> (Vec<Vertex>, Vec<Vector3<u32>>) {
+    let mut vertices = Vec::new();
+    let mut faces = Vec::new();
+
+    push_grid(
+        &mut vertices,
+        &mut faces,
+        Vector3::zeros(),
+        Vector3::new(size.x / 2.0, 0.0, 0.0),
+        Vector3::new(0.0, 0.0, -size.y / 2.0),
+        Vector3::y(),
+        Vector2::new(subdivisions.x.max(1), subdivisions.y.max(1)),
+    );
+
+    (vertices, faces)
+}
+
+/// A rectangle on the xy plane facing +z, centered on the origin
+pub fn quad(size: Vector2<f32>) -> (Vec<Vertex>, Vec<Vector3<u32>>) {
+    let mut vertices = Vec::with_capacity(4);
+    let mut faces = Vec::with_capacity(2);
+
+    push_grid(
+        &mut vertices,
+        &mut faces,
+        Vector3::zeros(),
+        Vector3::new(size.x / 2.0, 0.0, 0.0),
+        Vector3::new(0.0, size.y / 2.0, 0.0),
+        Vector3::z(),
+        Vector2::new(1, 1),
+    );
+
+    (vertices, faces)
+}
+
+/// A sphere made of `segments` slices around y and `rings` stacks from pole to pole
+pub fn uv_sphere(radius: f32, segments: u32, rings: u32) -> (Vec<Vertex>, Vec<Vector3<u32>>) {
+    let rings = rings.max(2);
+    let profile = (0..=rings)
+        .map(|ring| {
+            let angle = PI * (ring as f32) / (rings as f32);
+            // sin(PI) isn't exactly 0, the bottom pole has to land on the axis
+            let normal = if ring == rings {
+                Vector2::new(0.0, -1.0)
+            } else {
+                Vector2::new(angle.sin(), angle.cos())
+            };
+            ProfilePoint {
+                position: normal * radius,
+                normal,
+                v: 1.0 - (ring as f32) / (rings as f32),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let mut vertices = Vec::new();
+    let mut faces = Vec::new();
+    push_lathe(&mut vertices, &mut faces, &profile, segments);
+
+    (vertices, faces)
+}
+
+/// A sphere made by subdividing an icosahedron, which spreads the triangles evenly.
+/// The texture coordinates are a spherical projection and stretch along the seam at -z.
+pub fn icosphere(radius: f32, subdivisions: u32) -> (Vec<Vertex>, Vec<Vector3<u32>>) {
+    let t = (1.0 + 5.0_f32.sqrt()) / 2.0;
+    let mut positions = [
+        Vector3::new(-1.0, t, 0.0),
+        Vector3::new(1.0, t, 0.0),
+        Vector3::new(-1.0, -t, 0.0),
+        Vector3::new(1.0, -t, 0.0),
+        Vector3::new(0.0, -1.0, t),
+        Vector3::new(0.0, 1.0, t),
+        Vector3::new(0.0, -1.0, -t),
+        Vector3::new(0.0, 1.0, -t),
+        Vector3::new(t, 0.0, -1.0),
+        Vector3::new(t, 0.0, 1.0),
+        Vector3::new(-t, 0.0, -1.0),
+        Vector3::new(-t, 0.0, 1.0),
+    ]
+    .iter()
+    .map(|position| position.normalize())
+    .collect::<Vec<_>>();
+    let mut faces = [
+        [0, 11, 5],
+        [0, 5, 1],
+        [0, 1, 7],
+        [0, 7, 10],
+        [0, 10, 11],
+        [1, 5, 9],
+        [5, 11, 4],
+        [11, 10, 2],
+        [10, 7, 6],
+        [7, 1, 8],
+        [3, 9, 4],
+        [3, 4, 2],
+        [3, 2, 6],
+        [3, 6, 8],
+        [3, 8, 9],
+        [4, 9, 5],
+        [2, 4, 11],
+        [6, 2, 10],
+        [8, 6, 7],
+        [9, 8, 1],
+    ]
+    .iter()
+    .map(|face| Vector3::new(face[0], face[1], face[2]))
+    .collect::<Vec<Vector3<u32>>>();
+
+    for _ in 0..subdivisions {
+        let mut midpoints = HashMap::<(u32, u32), u32>::new();
+        let mut midpoint = |a: u32, b: u32| {
+            *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                positions.push(((positions[a as usize] + positions[b as usize]) / 2.0).normalize());
+                (positions.len() - 1) as u32
+            })
+        };
+
+        faces = faces
+            .iter()
+            .flat_map(|face| {
+                let ab = midpoint(face.x, face.y);
+                let bc = midpoint(face.y, face.z);
+                let ca = midpoint(face.z, face.x);
+                [
+                    Vector3::new(face.x, ab, ca),
+                    Vector3::new(face.y, bc, ab),
+                    Vector3::new(face.z, ca, bc),
+                    Vector3::new(ab, bc, ca),
+                ]
+            })
+            .collect();
+    }
+
+    let vertices = positions
+        .iter()
+        .map(|normal| {
+            Vertex::new(
+                normal * radius,
+                *normal,
+                Vector2::new(
+                    0.5 + normal.x.atan2(normal.z) / (2.0 * PI),
+                    0.5 + normal.y.asin() / PI,
+                ),
+            )
+        })
+        .collect();
+
+    (vertices, faces)
+}
+
+/// A capped cylinder along y, centered on the origin
+pub fn cylinder(radius: f32, height: f32, segments: u32) -> (Vec<Vertex>, Vec<Vector3<u32>>) {
+    let profile = [
+        ProfilePoint {
+            position: Vector2::new(radius, height / 2.0),
+            normal: Vector2::new(1.0, 0.0),
+            v: 1.0,
+        },
+        ProfilePoint {
+            position: Vector2::new(radius, -height / 2.0),
+            normal: Vector2::new(1.0, 0.0),
+            v: 0.0,
+        },
+    ];
+
+    let mut vertices = Vec::new();
+    let mut faces = Vec::new();
+    push_lathe(&mut vertices, &mut faces, &profile, segments);
+    push_disk(
+        &mut vertices,
+        &mut faces,
+        height / 2.0,
+        radius,
+        segments,
+        true,
+    );
+    push_disk(
+        &mut vertices,
+        &mut faces,
+        -height / 2.0,
+        radius,
+        segments,
+        false,
+    );
+
+    (vertices, faces)
+}
+
+/// A cone along y with its apex at +y, centered on the origin
+pub fn cone(radius: f32, height: f32, segments: u32) -> (Vec<Vertex>, Vec<Vector3<u32>>) {
+    let normal = Vector2::new(height, radius).normalize();
+    let profile = [
+        ProfilePoint {
+            position: Vector2::new(0.0, height / 2.0),
+            normal,
+            v: 1.0,
+        },
+        ProfilePoint {
+            position: Vector2::new(radius, -height / 2.0),
+            normal,
+            v: 0.0,
+        },
+    ];
+
+    let mut vertices = Vec::new();
+    let mut faces = Vec::new();
+    push_lathe(&mut vertices, &mut faces, &profile, segments);
+    push_disk(
+        &mut vertices,
+        &mut faces,
+        -height / 2.0,
+        radius,
+        segments,
+        false,
+    );
+
+    (vertices, faces)
+}
+
+/// A torus lying on the xz plane. `major_radius` is the distance from the center to the middle of the tube.
+pub fn torus(
+    major_radius: f32,
+    minor_radius: f32,
+    major_segments: u32,
+    minor_segments: u32,
+) -> (Vec<Vertex>, Vec<Vector3<u32>>) {
+    let minor_segments = minor_segments.max(3);
+    // Starts at the top of the tube and goes around the outside first
+    let profile = (0..=minor_segments)
+        .map(|segment| {
+            let angle = PI / 2.0 - 2.0 * PI * (segment as f32) / (minor_segments as f32);
+            let normal = Vector2::new(angle.cos(), angle.sin());
+            ProfilePoint {
+                position: Vector2::new(major_radius, 0.0) + normal * minor_radius,
+                normal,
+                v: 1.0 - (segment as f32) / (minor_segments as f32),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let mut vertices = Vec::new();
+    let mut faces = Vec::new();
+    push_lathe(&mut vertices, &mut faces, &profile, major_segments);
+
+    (vertices, faces)
+}
+
+/// A cylinder along y with hemispheres on both ends. `height` is the total height including the hemispheres.
+pub fn capsule(
+    radius: f32,
+    height: f32,
+    segments: u32,
+    hemisphere_rings: u32,
+) -> (Vec<Vertex>, Vec<Vector3<u32>>) {
+    let hemisphere_rings = hemisphere_rings.max(1);
+    let half_cylinder_height = (height / 2.0 - radius).max(0.0);
+    let total_length = PI * radius + 2.0 * half_cylinder_height;
+
+    let mut profile = Vec::with_capacity(((hemisphere_rings + 1) * 2) as usize);
+    for (offset, first_angle, arc_offset) in [
+        (half_cylinder_height, 0.0, 0.0),
+        (
+            -half_cylinder_height,
+            PI / 2.0,
+            PI / 2.0 * radius + 2.0 * half_cylinder_height,
+        ),
+    ] {
+        for ring in 0..=hemisphere_rings {
+            // Without a cylinder part both hemispheres share the equator
+            if first_angle > 0.0 && ring == 0 && half_cylinder_height == 0.0 {
+                continue;
+            }
+            let ring_angle = PI / 2.0 * (ring as f32) / (hemisphere_rings as f32);
+            let angle = first_angle + ring_angle;
+            let normal = if first_angle > 0.0 && ring == hemisphere_rings {
+                Vector2::new(0.0, -1.0)
+            } else {
+                Vector2::new(angle.sin(), angle.cos())
+            };
+            profile.push(ProfilePoint {
+                position: normal * radius + Vector2::new(0.0, offset),
+                normal,
+                v: 1.0 - (arc_offset + ring_angle * radius) / total_length,
+            });
+        }
+    }
+
+    let mut vertices = Vec::new();
+    let mut faces = Vec::new();
+    push_lathe(&mut vertices, &mut faces, &profile, segments);
+
+    (vertices, faces)
+}
+
+/// A point of a profile revolved around y. `position` and `normal` are given as (distance from y, y).
+struct ProfilePoint {
+    position: Vector2<f32>,
+    normal: Vector2<f32>,
+    v: f32,
+}
+
+/// Revolves a profile ordered from top to bottom around y. Triangles that collapse onto y are skipped.
+fn push_lathe(
+    vertices: &mut Vec<Vertex>,
+    faces: &mut Vec<Vector3<u32>>,
+    profile: &[ProfilePoint],
+    segments: u32,
+) {
+    let segments = segments.max(3);
+    let first_vertex = vertices.len() as u32;
+
+    for point in profile {
+        for segment in 0..=segments {
+            let angle = 2.0 * PI * (segment as f32) / (segments as f32);
+            let (sin, cos) = angle.sin_cos();
+            vertices.push(Vertex::new(
+                Vector3::new(
+                    point.position.x * sin,
+                    point.position.y,
+                    point.position.x * cos,
+                ),
+                Vector3::new(point.normal.x * sin, point.normal.y, point.normal.x * cos),
+                Vector2::new((segment as f32) / (segments as f32), point.v),
+            ));
+        }
+    }
+
+    for row in 0..(profile.len() - 1) {
+        let top_on_axis = profile[row].position.x == 0.0;
+        let bottom_on_axis = profile[row + 1].position.x == 0.0;
+
+        for segment in 0..segments {
+            let top = first_vertex + (row as u32) * (segments + 1) + segment;
+            let bottom = top + segments + 1;
+
+            if !top_on_axis {
+                faces.push(Vector3::new(top, bottom, top + 1));
+            }
+            if !bottom_on_axis {
+                faces.push(Vector3::new(top + 1, bottom, bottom + 1));
+            }
+        }
+    }
+}
+
+/// A flat disk on the xz plane at `y` facing +y or -y
+fn push_disk(
+    vertices: &mut Vec<Vertex>,
+    faces: &mut Vec<Vector3<u32>>,
+    y: f32,
+    radius: f32,
+    segments: u32,
+    facing_up: bool,
+) {
+    let segments = segments.max(3);
+    let normal = if facing_up {
+        Vector3::y()
+    } else {
+        -Vector3::y()
+    };
+    let center = vertices.len() as u32;
+
+    vertices.push(Vertex::new(
+        Vector3::new(0.0, y, 0.0),
+        normal,
+        Vector2::new(0.5, 0.5),
+    ));
+    for segment in 0..=segments {
+        let angle = 2.0 * PI * (segment as f32) / (segments as f32);
+        let (sin, cos) = angle.sin_cos();
+        // +z is at the bottom of the texture when looking down at the top disk
+        let v = if facing_up { -cos } else { cos };
+        vertices.push(Vertex::new(
+            Vector3::new(radius * sin, y, radius * cos),
+            normal,
+            Vector2::new(0.5 + sin / 2.0, 0.5 + v / 2.0),
+        ));
+    }
+
+    for segment in 0..segments {
+        let current = center + 1 + segment;
+        faces.push(if facing_up {
+            Vector3::new(center, current, current + 1)
+        } else {
+            Vector3::new(center, current + 1, current)
+        });
+    }
+}
+
+/// A grid spanning `center - u - v` to `center + u + v`. `u` cross `v` must point along `normal`.
+fn push_grid(
+    vertices: &mut Vec<Vertex>,
+    faces: &mut Vec<Vector3<u32>>,
+    center: Vector3<f32>,
+    u: Vector3<f32>,
+    v: Vector3<f32>,
+    normal: Vector3<f32>,
+    subdivisions: Vector2<u32>,
+) {
+    let first_vertex = vertices.len() as u32;
+
+    for y in 0..=subdivisions.y {
+        for x in 0..=subdivisions.x {
+            let tex_coord = Vector2::new(
+                (x as f32) / (subdivisions.x as f32),
+                (y as f32) / (subdivisions.y as f32),
+            );
+            vertices.push(Vertex::new(
+                center + u * (tex_coord.x * 2.0 - 1.0) + v * (tex_coord.y * 2.0 - 1.0),
+                normal,
+                tex_coord,
+            ));
+        }
+    }
+
+    for y in 0..subdivisions.y {
+        for x in 0..subdivisions.x {
+            let bottom_left = first_vertex + y * (subdivisions.x + 1) + x;
+            let top_left = bottom_left + subdivisions.x + 1;
+
+            faces.push(Vector3::new(bottom_left, bottom_left + 1, top_left + 1));
+            faces.push(Vector3::new(bottom_left, top_left + 1, top_left));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks the indices, normals, texture coordinates and winding, then returns the counts
+    fn check((vertices, faces): (Vec<Vertex>, Vec<Vector3<u32>>)) -> (usize, usize) {
+        for vertex in &vertices {
+            assert!(
+                (vertex.normal.norm() - 1.0).abs() < 1e-5,
+                "{:?}",
+                vertex.normal
+            );
+            assert!(
+                vertex
+                    .tex_coord
+                    .iter()
+                    .all(|component| (-1e-6..=1.0 + 1e-6).contains(component)),
+                "{:?}",
+                vertex.tex_coord
+            );
+        }
+
+        for face in &faces {
+            assert!(face.iter().all(|&index| (index as usize) < vertices.len()));
+
+            let [a, b, c] = [face.x, face.y, face.z].map(|index| &vertices[index as usize]);
+            let face_normal = (b.position - a.position)
+                .cross(&(c.position - a.position))
+                .try_normalize(1e-12)
+                .unwrap_or_else(|| panic!("{face:?} is degenerate"));
+            for vertex in [a, b, c] {
+                assert!(
+                    face_normal.dot(&vertex.normal) > 0.0,
+                    "{face:?} winds against its normals"
+                );
+            }
+        }
+
+        (vertices.len(), faces.len())
+    }
+
+    #[test]
+    fn cube() {
+        assert_eq!(check(super::cube(Vector3::new(1.0, 2.0, 3.0))), (24, 12));
+    }
+
+    #[test]
+    fn plane_and_quad() {
+        assert_eq!(
+            check(plane(Vector2::new(2.0, 1.0), Vector2::new(4, 3))),
+            (20, 24)
+        );
+        assert_eq!(
+            check(plane(Vector2::new(2.0, 1.0), Vector2::zeros())),
+            (4, 2)
+        );
+        assert_eq!(check(quad(Vector2::new(2.0, 1.0))), (4, 2));
+    }
+
+    #[test]
+    fn spheres() {
+        // The pole rows collapse into single triangles
+        assert_eq!(check(uv_sphere(1.5, 16, 8)), (9 * 17, 2 * 16 * 7));
+        assert_eq!(check(icosphere(1.5, 0)), (12, 20));
+        assert_eq!(check(icosphere(1.5, 2)), (162, 320));
+
+        let (vertices, _) = icosphere(1.5, 2);
+        assert!(vertices
+            .iter()
+            .all(|vertex| (vertex.position.norm() - 1.5).abs() < 1e-5));
+    }
+
+    #[test]
+    fn cylinder_and_cone() {
+        // The side, then a center and a closed ring for each cap
+        assert_eq!(check(cylinder(0.5, 2.0, 12)), (2 * 13 + 2 * 14, 4 * 12));
+        assert_eq!(check(cone(0.5, 2.0, 12)), (2 * 13 + 14, 2 * 12));
+    }
+
+    #[test]
+    fn torus() {
+        assert_eq!(check(super::torus(1.0, 0.25, 24, 8)), (25 * 9, 2 * 24 * 8));
+    }
+
+    #[test]
+    fn capsule() {
+        let rings = 4;
+        let rows = 2 * rings + 1;
+        assert_eq!(
+            check(super::capsule(0.5, 2.0, 12, rings)),
+            (
+                2 * (rings as usize + 1) * 13,
+                2 * 12 * rows as usize - 2 * 12
+            )
+        );
+        // Without a cylinder part it's a sphere
+        assert_eq!(
+            check(super::capsule(0.5, 1.0, 12, rings)),
+            check(uv_sphere(0.5, 12, 2 * rings))
+        );
+    }
+}
@@ -0,0 +1,361 @@
+use crate::{
+    na::{Vector2, Vector3},
+    Aabb,
+};
+
+const MAX_LEAF_TRIANGLES: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ray {
+    pub origin: Vector3<f32>,
+    /// Normalized
+    pub direction: Vector3<f32>,
+}
+
+impl Ray {
+    pub fn new(origin: Vector3<f32>, direction: Vector3<f32>) -> Self {
+        Self {
+            origin,
+            direction: direction.normalize(),
+        }
+    }
+
+    pub fn get_point(&self, distance: f32) -> Vector3<f32> {
+        self.origin + self.direction * distance
+    }
+
+    /// The distance to where the ray enters the box, 0 if it starts inside
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<f32> {
+        let mut enter = 0.0f32;
+        let mut exit = f32::INFINITY;
+
+        for axis in 0..3 {
+            let direction = self.direction[axis];
+            let (min, max) = (aabb.min[axis], aabb.max[axis]);
+
+            if direction == 0.0 {
+                // Parallel to the slab, `0 * inf` would be NaN on its planes
+                if self.origin[axis] < min || self.origin[axis] > max {
+                    return None;
+                }
+            } else {
+                let near = (min - self.origin[axis]) / direction;
+                let far = (max - self.origin[axis]) / direction;
+                enter = enter.max(near.min(far));
+                exit = exit.min(near.max(far));
+            }
+        }
+
+        (enter <= exit).then_some(enter)
+    }
+
+    /// Möller–Trumbore, hits both sides. Returns the distance and the barycentric coordinates.
+    pub fn intersect_triangle(
+        &self,
+        a: &Vector3<f32>,
+        b: &Vector3<f32>,
+        c: &Vector3<f32>,
+    ) -> Option<(f32, Vector3<f32>)> {
+        let edge_ab = b - a;
+        let edge_ac = c - a;
+        let p = self.direction.cross(&edge_ac);
+        let determinant = edge_ab.dot(&p);
+
+        if determinant.abs() <= f32::EPSILON {
+            return None;
+        }
+
+        let inverse_determinant = 1.0 / determinant;
+        let to_origin = self.origin - a;
+        let u = to_origin.dot(&p) * inverse_determinant;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let q = to_origin.cross(&edge_ab);
+        let v = self.direction.dot(&q) * inverse_determinant;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let distance = edge_ac.dot(&q) * inverse_determinant;
+
+        (distance >= 0.0).then_some((distance, Vector3::new(1.0 - u - v, u, v)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hit {
+    pub distance: f32,
+    pub point: Vector3<f32>,
+    /// Into `MeshData::get_triangles`
+    pub triangle_index: usize,
+    /// The weights of the three vertices of the triangle
+    pub barycentric: Vector3<f32>,
+    /// Interpolated from the vertex normals, or the face normal if the vertices have none
+    pub normal: Vector3<f32>,
+    pub tex_coord: Option<Vector2<f32>>,
+}
+
+#[derive(Debug, Clone)]
+enum BvhNode {
+    Leaf {
+        aabb: Aabb,
+        start: usize,
+        end: usize,
+    },
+    Branch {
+        aabb: Aabb,
+        left: usize,
+        right: usize,
+    },
+}
+
+impl BvhNode {
+    fn get_aabb(&self) -> &Aabb {
+        match self {
+            BvhNode::Leaf { aabb, .. } | BvhNode::Branch { aabb, .. } => aabb,
+        }
+    }
+}
+
+/// A bounding volume hierarchy over the triangles of a mesh, split at the median of the longest axis
+#[derive(Debug, Clone)]
+pub struct Bvh {
+    positions: Vec<Vector3<f32>>,
+    triangles: Vec<Vector3<u32>>,
+    /// Indices into `triangles`, ordered so every leaf owns a contiguous range
+    order: Vec<usize>,
+    nodes: Vec<BvhNode>,
+}
+
+impl Bvh {
+    pub fn new(positions: &[Vector3<f32>], triangles: &[Vector3<u32>]) -> Self {
+        let mut bvh = Self {
+            positions: positions.to_vec(),
+            triangles: triangles.to_vec(),
+            order: (0..triangles.len()).collect(),
+            nodes: Vec::new(),
+        };
+
+        if !triangles.is_empty() {
+            bvh.build(0, triangles.len());
+        }
+
+        bvh
+    }
+
+    /// The closest triangle hit, with its distance and barycentric coordinates
+    pub fn raycast(&self, ray: &Ray) -> Option<(usize, f32, Vector3<f32>)> {
+        let mut closest: Option<(usize, f32, Vector3<f32>)> = None;
+        let mut stack = if self.nodes.is_empty() {
+            Vec::new()
+        } else {
+            vec![0]
+        };
+
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            match ray.intersect_aabb(node.get_aabb()) {
+                Some(distance) if closest.is_none_or(|(_, closest, _)| distance <= closest) => (),
+                _ => continue,
+            }
+
+            match *node {
+                BvhNode::Leaf { start, end, .. } => {
+                    for &triangle_index in &self.order[start..end] {
+                        let [a, b, c] = self.get_triangle_positions(triangle_index);
+                        if let Some((distance, barycentric)) = ray.intersect_triangle(&a, &b, &c) {
+                            if closest.is_none_or(|(_, closest, _)| distance < closest) {
+                                closest = Some((triangle_index, distance, barycentric));
+                            }
+                        }
+                    }
+                }
+                BvhNode::Branch { left, right, .. } => {
+                    stack.push(left);
+                    stack.push(right);
+                }
+            }
+        }
+
+        closest
+    }
+
+    pub fn get_triangles(&self) -> &[Vector3<u32>] {
+        &self.triangles
+    }
+
+    pub fn get_aabb(&self) -> Aabb {
+        self.nodes
+            .first()
+            .map_or_else(Aabb::default, |node| *node.get_aabb())
+    }
+
+    fn get_triangle_positions(&self, triangle_index: usize) -> [Vector3<f32>; 3] {
+        let triangle = self.triangles[triangle_index];
+        [
+            self.positions[triangle.x as usize],
+            self.positions[triangle.y as usize],
+            self.positions[triangle.z as usize],
+        ]
+    }
+
+    /// Builds the node for `order[start..end]` and returns its index
+    fn build(&mut self, start: usize, end: usize) -> usize {
+        let positions = self.order[start..end]
+            .iter()
+            .flat_map(|&triangle_index| self.get_triangle_positions(triangle_index))
+            .collect::<Vec<_>>();
+        let aabb = Aabb::from_points(&positions);
+        let node_index = self.nodes.len();
+
+        if end - start <= MAX_LEAF_TRIANGLES {
+            self.nodes.push(BvhNode::Leaf { aabb, start, end });
+            return node_index;
+        }
+
+        let centroids = self.order[start..end]
+            .iter()
+            .map(|&triangle_index| {
+                let [a, b, c] = self.get_triangle_positions(triangle_index);
+                (a + b + c) / 3.0
+            })
+            .collect::<Vec<_>>();
+        let axis = Aabb::from_points(&centroids).get_size().imax();
+
+        let centroid = |bvh: &Self, triangle_index: usize| {
+            let [a, b, c] = bvh.get_triangle_positions(triangle_index);
+            a[axis] + b[axis] + c[axis]
+        };
+        let mut order = self.order[start..end].to_vec();
+        order.sort_by(|&a, &b| centroid(self, a).total_cmp(&centroid(self, b)));
+        self.order[start..end].copy_from_slice(&order);
+
+        // Reserves the slot so the children come after their parent
+        self.nodes.push(BvhNode::Leaf { aabb, start, end });
+        let middle = start + (end - start) / 2;
+        let left = self.build(start, middle);
+        let right = self.build(middle, end);
+        self.nodes[node_index] = BvhNode::Branch { aabb, left, right };
+
+        node_index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{na::Vector2, primitives};
+
+    fn brute_force(
+        ray: &Ray,
+        positions: &[Vector3<f32>],
+        triangles: &[Vector3<u32>],
+    ) -> Option<(usize, f32)> {
+        triangles
+            .iter()
+            .enumerate()
+            .filter_map(|(i, triangle)| {
+                let [a, b, c] = [triangle.x, triangle.y, triangle.z].map(|i| positions[i as usize]);
+                ray.intersect_triangle(&a, &b, &c)
+                    .map(|(distance, _)| (i, distance))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+    }
+
+    fn assert_matches_brute_force(
+        positions: &[Vector3<f32>],
+        triangles: &[Vector3<u32>],
+        rays: &[Ray],
+    ) {
+        let bvh = Bvh::new(positions, triangles);
+
+        for ray in rays {
+            let expected = brute_force(ray, positions, triangles);
+            let actual = bvh.raycast(ray);
+
+            match (expected, actual) {
+                (None, None) => (),
+                (Some((_, expected)), Some((_, actual, _))) => {
+                    assert!(
+                        (expected - actual).abs() < 1e-5,
+                        "{expected} != {actual} for {ray:?}"
+                    )
+                }
+                _ => panic!("{expected:?} != {actual:?} for {ray:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn axis_aligned_rays_on_slab_planes_hit() {
+        let (vertices, faces) = primitives::plane(Vector2::new(2.0, 2.0), Vector2::new(8, 8));
+        let positions = vertices
+            .iter()
+            .map(|vertex| vertex.position)
+            .collect::<Vec<_>>();
+        let bvh = Bvh::new(&positions, &faces);
+
+        let ray = Ray::new(Vector3::new(0.3, 1.0, 0.0), -Vector3::y());
+        let (_, distance, _) = bvh.raycast(&ray).expect("The ray should hit the plane");
+        assert!((distance - 1.0).abs() < 1e-5);
+
+        // Every grid line is a slab plane of some node
+        let rays = (0..=8)
+            .flat_map(|x| (0..=8).map(move |z| (x, z)))
+            .map(|(x, z)| {
+                Ray::new(
+                    Vector3::new(x as f32 / 4.0 - 1.0, 1.0, z as f32 / 4.0 - 1.0),
+                    -Vector3::y(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_matches_brute_force(&positions, &faces, &rays);
+    }
+
+    #[test]
+    fn bvh_matches_brute_force() {
+        let (vertices, faces) = primitives::icosphere(1.0, 2);
+        let positions = vertices
+            .iter()
+            .map(|vertex| vertex.position)
+            .collect::<Vec<_>>();
+
+        // A deterministic spread of rays, some missing the sphere
+        let rays = (0..200)
+            .map(|i| {
+                let i = i as f32;
+                let origin =
+                    Vector3::new((i * 0.37).sin(), (i * 0.73).cos(), (i * 0.11).sin()) * 3.0;
+                let target = Vector3::new((i * 1.3).cos(), (i * 0.5).sin(), (i * 0.9).cos()) * 1.2;
+                Ray::new(origin, target - origin)
+            })
+            .chain([
+                Ray::new(Vector3::new(0.0, 0.0, 5.0), -Vector3::z()),
+                Ray::new(Vector3::new(0.0, 0.0, 0.0), Vector3::x()),
+                Ray::new(Vector3::new(5.0, 5.0, 5.0), Vector3::x()),
+            ])
+            .collect::<Vec<_>>();
+
+        assert_matches_brute_force(&positions, &faces, &rays);
+    }
+
+    #[test]
+    fn parallel_rays_outside_the_box_miss() {
+        let aabb = Aabb::new(Vector3::zeros(), Vector3::repeat(1.0));
+
+        assert!(Ray::new(Vector3::new(2.0, 0.5, -1.0), Vector3::z())
+            .intersect_aabb(&aabb)
+            .is_none());
+        assert_eq!(
+            Ray::new(Vector3::new(1.0, 0.5, -1.0), Vector3::z()).intersect_aabb(&aabb),
+            Some(1.0)
+        );
+        assert_eq!(
+            Ray::new(Vector3::new(0.5, 0.5, 0.5), Vector3::z()).intersect_aabb(&aabb),
+            Some(0.0)
+        );
+    }
+}
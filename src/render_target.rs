@@ -0,0 +1,182 @@
+use crate::{na::Vector2, FilterMode, Texture};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DepthFormat {
+    Fixed24,
+    /// Needed for `DepthMode::ReverseZ` to be worth anything
+    Float32,
+}
+
+impl DepthFormat {
+    fn internal_format(&self) -> u32 {
+        match self {
+            DepthFormat::Fixed24 => gl::DEPTH_COMPONENT24,
+            DepthFormat::Float32 => gl::DEPTH_COMPONENT32F,
+        }
+    }
+
+    fn data_type(&self) -> u32 {
+        match self {
+            DepthFormat::Fixed24 => gl::UNSIGNED_INT,
+            DepthFormat::Float32 => gl::FLOAT,
+        }
+    }
+}
+
+/// An offscreen framebuffer with a color and a depth texture
+pub struct RenderTarget {
+    fbo: u32,
+    color_texture: Texture,
+    depth_texture: Texture,
+    depth_format: DepthFormat,
+    size: Vector2<u32>,
+}
+
+impl RenderTarget {
+    /// Returns the framebuffer status if the framebuffer is incomplete
+    pub fn new(size: Vector2<u32>, depth_format: DepthFormat) -> Result<Self, u32> {
+        let (color_texture, depth_texture) = Self::create_attachments(size, depth_format);
+        let mut render_target = Self {
+            fbo: 0,
+            color_texture,
+            depth_texture,
+            depth_format,
+            size,
+        };
+
+        unsafe {
+            gl::GenFramebuffers(1, &mut render_target.fbo);
+        }
+        render_target.attach()?;
+
+        Ok(render_target)
+    }
+
+    pub fn get_id(&self) -> u32 {
+        self.fbo
+    }
+
+    pub fn get_color_texture(&self) -> &Texture {
+        &self.color_texture
+    }
+
+    pub fn get_depth_texture(&self) -> &Texture {
+        &self.depth_texture
+    }
+
+    pub fn get_depth_format(&self) -> DepthFormat {
+        self.depth_format
+    }
+
+    pub fn get_size(&self) -> Vector2<u32> {
+        self.size
+    }
+
+    /// Renders into this target and sets the viewport to cover it
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl::Viewport(0, 0, self.size.x as i32, self.size.y as i32);
+        }
+    }
+
+    /// Renders into the window again
+    pub fn unbind() {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+    }
+
+    /// Copies the color attachment into the window, stretched over `screen_size`
+    pub fn blit_to_screen(&self, screen_size: Vector2<u32>) {
+        unsafe {
+            gl::BlitNamedFramebuffer(
+                self.fbo,
+                0,
+                0,
+                0,
+                self.size.x as i32,
+                self.size.y as i32,
+                0,
+                0,
+                screen_size.x as i32,
+                screen_size.y as i32,
+                gl::COLOR_BUFFER_BIT,
+                gl::LINEAR,
+            );
+        }
+    }
+
+    /// Recreates the attachments, their previous contents are lost
+    pub fn resize(&mut self, size: Vector2<u32>) -> Result<(), u32> {
+        if size == self.size {
+            return Ok(());
+        }
+
+        (self.color_texture, self.depth_texture) =
+            Self::create_attachments(size, self.depth_format);
+        self.size = size;
+
+        self.attach()
+    }
+
+    fn create_attachments(size: Vector2<u32>, depth_format: DepthFormat) -> (Texture, Texture) {
+        (
+            Texture::empty(
+                size.x,
+                size.y,
+                gl::RGBA16F,
+                gl::RGBA,
+                gl::FLOAT,
+                FilterMode::Linear,
+            ),
+            Texture::empty(
+                size.x,
+                size.y,
+                depth_format.internal_format(),
+                gl::DEPTH_COMPONENT,
+                depth_format.data_type(),
+                FilterMode::Nearest,
+            ),
+        )
+    }
+
+    fn attach(&self) -> Result<(), u32> {
+        let status = unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::TEXTURE_2D,
+                self.color_texture.get_id(),
+                0,
+            );
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::DEPTH_ATTACHMENT,
+                gl::TEXTURE_2D,
+                self.depth_texture.get_id(),
+                0,
+            );
+            let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+
+            status
+        };
+
+        if status != gl::FRAMEBUFFER_COMPLETE {
+            return Err(status);
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for RenderTarget {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteFramebuffers(1, &self.fbo);
+        }
+    }
+}
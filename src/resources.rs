@@ -0,0 +1,127 @@
+use std::{
+    borrow::Borrow,
+    collections::HashMap,
+    error::Error,
+    fmt,
+    hash::Hash,
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Where model loaders read files and the resources they reference, like MTL files and textures
+pub trait ResourceProvider {
+    fn load(&self, path: &Path) -> io::Result<Vec<u8>>;
+}
+
+/// Reads files relative to a root directory
+#[derive(Debug, Clone, Default)]
+pub struct FileSystemProvider {
+    pub root: PathBuf,
+}
+
+impl FileSystemProvider {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl ResourceProvider for FileSystemProvider {
+    fn load(&self, path: &Path) -> io::Result<Vec<u8>> {
+        std::fs::read(self.root.join(path))
+    }
+}
+
+/// Looks up the path with `/` separators, then only the file name, so embedded resources
+/// (e.g. from `include_bytes!`) don't need to mirror the directory layout
+impl<K, V> ResourceProvider for HashMap<K, V>
+where
+    K: Borrow<str> + Hash + Eq,
+    V: AsRef<[u8]>,
+{
+    fn load(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let full_path = path.to_string_lossy().replace('\\', "/");
+        let file_name = path.file_name().map(|name| name.to_string_lossy());
+
+        self.get(full_path.as_str())
+            .or_else(|| file_name.and_then(|name| self.get(name.as_ref())))
+            .map(|data| data.as_ref().to_vec())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, full_path))
+    }
+}
+
+#[derive(Debug)]
+pub enum ModelError {
+    /// A file the model needs couldn't be loaded
+    Resource(PathBuf, io::Error),
+    Obj(tobj::LoadError),
+    Image(PathBuf, image::ImageError),
+    Gltf(gltf::Error),
+    /// The file parsed but its contents are inconsistent
+    Invalid(String),
+}
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModelError::Resource(path, error) => {
+                write!(f, "Couldn't load {}: {error}", path.display())
+            }
+            ModelError::Obj(error) => write!(f, "Invalid OBJ: {error}"),
+            ModelError::Image(path, error) => {
+                write!(f, "Couldn't decode {}: {error}", path.display())
+            }
+            ModelError::Gltf(error) => write!(f, "Invalid glTF: {error}"),
+            ModelError::Invalid(message) => f.write_str(message),
+        }
+    }
+}
+
+impl Error for ModelError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ModelError::Resource(_, error) => Some(error),
+            ModelError::Obj(error) => Some(error),
+            ModelError::Image(_, error) => Some(error),
+            ModelError::Gltf(error) => Some(error),
+            ModelError::Invalid(_) => None,
+        }
+    }
+}
+
+pub(crate) fn load_resource(
+    resources: &impl ResourceProvider,
+    path: &Path,
+) -> Result<Vec<u8>, ModelError> {
+    resources
+        .load(path)
+        .map_err(|error| ModelError::Resource(PathBuf::from(path), error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_map_falls_back_to_the_file_name() {
+        let resources = HashMap::from([("textures/a.png", vec![1]), ("b.png", vec![2])]);
+
+        assert_eq!(
+            resources.load(Path::new("textures/a.png")).unwrap(),
+            vec![1]
+        );
+        assert_eq!(resources.load(Path::new("models/b.png")).unwrap(), vec![2]);
+        assert_eq!(
+            resources.load(Path::new("c.png")).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+    }
+
+    #[test]
+    fn errors_name_the_missing_resource() {
+        let resources = HashMap::<&str, Vec<u8>>::new();
+        let error = load_resource(&resources, Path::new("model.mtl")).unwrap_err();
+
+        assert!(error.to_string().contains("model.mtl"));
+        assert!(error.source().is_some());
+    }
+}
@@ -0,0 +1,160 @@
+use crate::{
+    na::{Vector2, Vector3},
+    Camera, CameraType, GameObject, RenderTarget,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Eye {
+    Left,
+    Right,
+}
+
+/// Renders a perspective camera once per eye with off-axis frustums that converge at `convergence_distance`
+#[derive(Clone, Copy)]
+pub struct StereoRig {
+    pub camera: Camera,
+    /// The distance between the eyes in world units
+    pub interpupillary_distance: f32,
+    /// The distance at which both eyes see the same image, objects here appear at screen depth
+    pub convergence_distance: f32,
+}
+
+impl StereoRig {
+    pub fn new(camera: Camera, interpupillary_distance: f32, convergence_distance: f32) -> Self {
+        Self {
+            camera,
+            interpupillary_distance,
+            convergence_distance,
+        }
+    }
+
+    /// Returns the camera for one eye with its projection built for the given aspect.
+    /// Non-perspective cameras are returned unchanged.
+    pub fn get_eye_camera(&self, eye: Eye, aspect: f32) -> Camera {
+        let CameraType::Perspective(perspective) = self.camera.camera_type else {
+            return self.camera;
+        };
+
+        let side = match eye {
+            Eye::Left => -1.0,
+            Eye::Right => 1.0,
+        };
+        let half_separation = side * self.interpupillary_distance / 2.0;
+        let near = perspective.znear();
+
+        let top = near * (perspective.fovy() / 2.0).tan();
+        let half_width = top * aspect;
+        let shift = half_separation * near / self.convergence_distance;
+
+        let (left, right) = (-half_width - shift, half_width - shift);
+        let mut eye_camera = self.camera;
+        eye_camera.camera_type = CameraType::Custom(if self.camera.is_reverse_z() {
+            Camera::reverse_z_frustum_matrix(left, right, -top, top, near)
+        } else {
+            Camera::frustum_matrix(left, right, -top, top, near, perspective.zfar())
+        });
+        eye_camera.transform.position +=
+            self.camera.transform.rotation * Vector3::new(half_separation, 0.0, 0.0);
+
+        eye_camera
+    }
+
+    /// Renders the left eye into the left half of the camera's viewport and the right eye into the right half
+    pub fn render_side_by_side(&self, objects: &mut [&mut dyn GameObject]) {
+        let (position, size) = self.camera.get_viewport();
+        let eye_size = Vector2::new(size.x / 2, size.y);
+        let aspect = (eye_size.x as f32) / (eye_size.y as f32);
+
+        self.camera.clear();
+
+        for (eye, offset) in [(Eye::Left, 0), (Eye::Right, eye_size.x as i32)] {
+            let mut eye_camera = self.get_eye_camera(eye, aspect);
+            eye_camera.set_viewport(Vector2::new(position.x + offset, position.y), eye_size);
+            eye_camera.draw_objects(objects);
+        }
+    }
+
+    /// Renders each eye into its own render target
+    pub fn render_to_targets(
+        &self,
+        left_target: &RenderTarget,
+        right_target: &RenderTarget,
+        objects: &mut [&mut dyn GameObject],
+    ) {
+        for (eye, target) in [(Eye::Left, left_target), (Eye::Right, right_target)] {
+            let size = target.get_size();
+            let mut eye_camera = self.get_eye_camera(eye, (size.x as f32) / (size.y as f32));
+            eye_camera.set_viewport(Vector2::zeros(), size);
+
+            target.bind();
+            eye_camera.clear();
+            eye_camera.draw_objects(objects);
+        }
+
+        RenderTarget::unbind();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{na::Point3, DepthMode};
+    use palette::LinSrgba;
+
+    fn project_depth(camera: &Camera, distance: f32) -> f32 {
+        camera
+            .get_unjittered_projection_matrix()
+            .transform_point(&Point3::new(0.0, 0.0, -distance))
+            .z
+    }
+
+    #[test]
+    fn eye_cameras_keep_the_depth_mode() {
+        let camera = Camera::new_perspective(
+            60.0,
+            Vector2::new(800, 600),
+            0.1,
+            100.0,
+            LinSrgba::new(0.0, 0.0, 0.0, 1.0),
+        );
+
+        for eye in [Eye::Left, Eye::Right] {
+            let rig = StereoRig::new(camera, 0.064, 2.0);
+            let eye_camera = rig.get_eye_camera(eye, 4.0 / 3.0);
+            assert!(!eye_camera.is_reverse_z());
+            assert!((project_depth(&eye_camera, 0.1) + 1.0).abs() < 1e-4);
+            assert!((project_depth(&eye_camera, 100.0) - 1.0).abs() < 1e-4);
+
+            let rig = StereoRig::new(camera.with_depth_mode(DepthMode::ReverseZ), 0.064, 2.0);
+            let eye_camera = rig.get_eye_camera(eye, 4.0 / 3.0);
+            assert!(eye_camera.is_reverse_z());
+            assert!((project_depth(&eye_camera, 0.1) - 1.0).abs() < 1e-4);
+            assert!(project_depth(&eye_camera, 100.0) > 0.0);
+            assert!(project_depth(&eye_camera, 100.0) < project_depth(&eye_camera, 1.0));
+            assert!(project_depth(&eye_camera, 1e9) < 1e-6);
+            assert!((eye_camera.get_near_clipping_plane() - 0.1).abs() < 1e-6);
+            assert_eq!(eye_camera.get_far_clipping_plane(), f32::INFINITY);
+        }
+    }
+
+    #[test]
+    fn eye_frustums_converge() {
+        let camera = Camera::new_perspective(
+            60.0,
+            Vector2::new(800, 600),
+            0.1,
+            100.0,
+            LinSrgba::new(0.0, 0.0, 0.0, 1.0),
+        );
+        let rig = StereoRig::new(camera, 0.064, 2.0);
+
+        // A point on the center line at the convergence distance lands in the center of both eyes
+        for eye in [Eye::Left, Eye::Right] {
+            let eye_camera = rig.get_eye_camera(eye, 4.0 / 3.0);
+            let ndc = eye_camera
+                .get_view_projection_matrix()
+                .transform_point(&Point3::new(0.0, 0.0, -2.0));
+            assert!(ndc.x.abs() < 1e-5);
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use std::ffi::CString;
+
+use crate::{
+    na::{Matrix4, Vector2, Vector3, Vector4},
+    Camera, DepthFormat, Mesh, ProgramValue, RenderTarget, Shader, ShaderProgram, ShaderType,
+    Texture, UsageType, Vertex,
+};
+
+const VERTEX_SHADER: &[u8] = b"#version 460 core
+layout (location = 0) in vec3 position;
+layout (location = 2) in vec2 tex_coord;
+
+out vec2 uv;
+
+void main() {
+    uv = tex_coord;
+    gl_Position = vec4(position.xy, 0.0, 1.0);
+}
+";
+
+const FRAGMENT_SHADER: &[u8] = b"#version 460 core
+in vec2 uv;
+
+out vec4 frag_color;
+
+// Current color, history, current depth, velocity
+uniform sampler2D texture0;
+uniform sampler2D texture1;
+uniform sampler2D texture2;
+uniform sampler2D texture3;
+// Previous view projection * inverse current view projection
+uniform mat4 reprojection;
+// x: history weight, y: has history, z: use velocity, w: reverse z
+uniform vec4 params;
+
+void main() {
+    vec2 texel_size = 1.0 / vec2(textureSize(texture0, 0));
+    vec3 current = texture(texture0, uv).rgb;
+
+    vec3 neighborhood_min = current;
+    vec3 neighborhood_max = current;
+    for (int x = -1; x <= 1; x++) {
+        for (int y = -1; y <= 1; y++) {
+            vec3 neighbor = texture(texture0, uv + vec2(x, y) * texel_size).rgb;
+            neighborhood_min = min(neighborhood_min, neighbor);
+            neighborhood_max = max(neighborhood_max, neighbor);
+        }
+    }
+
+    vec2 history_uv;
+    if (params.z > 0.5) {
+        history_uv = uv - texture(texture3, uv).rg;
+    } else {
+        float depth = texture(texture2, uv).r;
+        float ndc_depth = params.w > 0.5 ? depth : depth * 2.0 - 1.0;
+        vec4 previous = reprojection * vec4(uv * 2.0 - 1.0, ndc_depth, 1.0);
+        history_uv = (previous.xy / previous.w) * 0.5 + 0.5;
+    }
+
+    if (params.y < 0.5 || any(lessThan(history_uv, vec2(0.0))) || any(greaterThan(history_uv, vec2(1.0)))) {
+        frag_color = vec4(current, 1.0);
+        return;
+    }
+
+    vec3 history = clamp(texture(texture1, history_uv).rgb, neighborhood_min, neighborhood_max);
+    frag_color = vec4(mix(current, history, params.x), 1.0);
+}
+";
+
+#[derive(Debug)]
+pub enum TaaError {
+    Shader(CString),
+    /// The framebuffer status of an incomplete history target
+    RenderTarget(u32),
+}
+
+/// Blends the jittered frames of a camera with `jitter_enabled` into an anti-aliased history.
+/// Neighborhood clamping of the history keeps ghosting down.
+pub struct TaaResolver {
+    /// How much of the history is kept each frame, between 0 and 1
+    pub history_weight: f32,
+    shader_program: ShaderProgram,
+    quad: Mesh,
+    history: [RenderTarget; 2],
+    current_history: usize,
+    has_history: bool,
+}
+
+impl TaaResolver {
+    pub fn new(size: Vector2<u32>) -> Result<Self, TaaError> {
+        let vertex_shader = Shader::from_source(VERTEX_SHADER, ShaderType::VertexShader)
+            .map_err(TaaError::Shader)?;
+        let fragment_shader = Shader::from_source(FRAGMENT_SHADER, ShaderType::FragmentShader)
+            .map_err(TaaError::Shader)?;
+        let shader_program = ShaderProgram::from_shaders(&vertex_shader, &fragment_shader)
+            .map_err(TaaError::Shader)?;
+
+        let quad = Mesh::from_vertices(
+            &[
+                Vertex::tex(Vector3::new(1.0, 1.0, 0.0), Vector2::new(1.0, 1.0)),
+                Vertex::tex(Vector3::new(1.0, -1.0, 0.0), Vector2::new(1.0, 0.0)),
+                Vertex::tex(Vector3::new(-1.0, -1.0, 0.0), Vector2::new(0.0, 0.0)),
+                Vertex::tex(Vector3::new(-1.0, 1.0, 0.0), Vector2::new(0.0, 1.0)),
+            ],
+            &[Vector3::new(0, 3, 1), Vector3::new(1, 3, 2)],
+            UsageType::Static,
+        );
+
+        Ok(Self {
+            history_weight: 0.9,
+            shader_program,
+            quad,
+            history: [Self::create_history(size)?, Self::create_history(size)?],
+            current_history: 0,
+            has_history: false,
+        })
+    }
+
+    /// Forgets the history, e.g. after a camera cut
+    pub fn reset(&mut self) {
+        self.has_history = false;
+    }
+
+    /// Resolves `scene`, which was rendered by `camera` after its `next_frame`, and returns the anti-aliased result.
+    /// `velocity` holds the screen space motion of each pixel since the last frame in UV units (current - previous).
+    /// Without it the history is reprojected using the scene depth, which only accounts for camera motion.
+    /// Unbinds the render target and leaves the depth test as it was.
+    pub fn resolve(
+        &mut self,
+        camera: &Camera,
+        scene: &RenderTarget,
+        velocity: Option<&Texture>,
+    ) -> Result<&RenderTarget, TaaError> {
+        let size = scene.get_size();
+        for history in &mut self.history {
+            if history.get_size() != size {
+                history.resize(size).map_err(TaaError::RenderTarget)?;
+                self.has_history = false;
+            }
+        }
+
+        let reprojection = camera.get_previous_view_projection_matrix()
+            * camera
+                .get_view_projection_matrix()
+                .try_inverse()
+                .unwrap_or_else(Matrix4::identity);
+
+        self.shader_program
+            .set_value("reprojection", ProgramValue::Mat4(reprojection));
+        self.shader_program.set_value(
+            "params",
+            ProgramValue::Vec4(Vector4::new(
+                self.history_weight,
+                if self.has_history { 1.0 } else { 0.0 },
+                if velocity.is_some() { 1.0 } else { 0.0 },
+                if camera.is_reverse_z() { 1.0 } else { 0.0 },
+            )),
+        );
+
+        let previous_history = &self.history[self.current_history];
+        let next_history = &self.history[1 - self.current_history];
+
+        next_history.bind();
+        // Restored afterwards so later draws in the frame keep their depth test
+        let depth_test = unsafe { gl::IsEnabled(gl::DEPTH_TEST) } == gl::TRUE;
+        unsafe {
+            gl::Disable(gl::DEPTH_TEST);
+        }
+        let mut textures = vec![
+            scene.get_color_texture(),
+            previous_history.get_color_texture(),
+            scene.get_depth_texture(),
+        ];
+        if let Some(velocity) = velocity {
+            textures.push(velocity);
+        }
+        self.quad.draw(&textures);
+        RenderTarget::unbind();
+        if depth_test {
+            unsafe {
+                gl::Enable(gl::DEPTH_TEST);
+            }
+        }
+
+        self.current_history = 1 - self.current_history;
+        self.has_history = true;
+
+        Ok(&self.history[self.current_history])
+    }
+
+    fn create_history(size: Vector2<u32>) -> Result<RenderTarget, TaaError> {
+        RenderTarget::new(size, DepthFormat::Fixed24).map_err(TaaError::RenderTarget)
+    }
+}
@@ -0,0 +1,191 @@
+//! MikkTSpace tangent generation, matching what Blender and most bakers use for normal maps
+
+use bevy_mikktspace::Geometry;
+
+use crate::{
+    na::{Vector3, Vector4},
+    TangentVertex, Vertex,
+};
+
+struct MikkGeometry<'a> {
+    vertices: &'a [Vertex],
+    faces: &'a [Vector3<u32>],
+    corner_tangents: Vec<Vector4<f32>>,
+}
+
+impl Geometry for MikkGeometry<'_> {
+    fn num_faces(&self) -> usize {
+        self.faces.len()
+    }
+
+    fn num_vertices_of_face(&self, _face: usize) -> usize {
+        3
+    }
+
+    fn position(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.vertex(face, vert).position.into()
+    }
+
+    fn normal(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.vertex(face, vert).normal.into()
+    }
+
+    fn tex_coord(&self, face: usize, vert: usize) -> [f32; 2] {
+        self.vertex(face, vert).tex_coord.into()
+    }
+
+    fn set_tangent_encoded(&mut self, tangent: [f32; 4], face: usize, vert: usize) {
+        self.corner_tangents[face * 3 + vert] = tangent.into();
+    }
+}
+
+impl MikkGeometry<'_> {
+    fn vertex(&self, face: usize, vert: usize) -> &Vertex {
+        &self.vertices[self.faces[face][vert] as usize]
+    }
+}
+
+/// Generates a tangent and bitangent sign for every vertex. The mesh needs normals and texture coordinates.
+/// Vertices that get different tangents from different faces (e.g. at mirrored UV seams) are split,
+/// the copies are appended so the original indices stay valid. If generation fails, e.g. because there are no faces, the tangents are left at zero.
+pub fn generate_tangents(
+    vertices: &[Vertex],
+    faces: &[Vector3<u32>],
+) -> (Vec<TangentVertex>, Vec<Vector3<u32>>) {
+    let mut geometry = MikkGeometry {
+        vertices,
+        faces,
+        corner_tangents: vec![Vector4::zeros(); faces.len() * 3],
+    };
+    bevy_mikktspace::generate_tangents(&mut geometry);
+
+    let mut new_vertices = vertices
+        .iter()
+        .map(|vertex| {
+            TangentVertex::new(
+                vertex.position,
+                vertex.normal,
+                vertex.tex_coord,
+                Vector4::zeros(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let mut new_faces = Vec::with_capacity(faces.len());
+    // The tangents each original vertex has been emitted with
+    let mut emitted = vec![Vec::<(Vector4<f32>, u32)>::new(); vertices.len()];
+
+    for (face_index, face) in faces.iter().enumerate() {
+        let mut new_face = Vector3::zeros();
+
+        for corner in 0..3 {
+            let vertex_index = face[corner] as usize;
+            let tangent = geometry.corner_tangents[face_index * 3 + corner];
+
+            new_face[corner] = match emitted[vertex_index]
+                .iter()
+                .find(|(emitted_tangent, _)| (emitted_tangent - tangent).norm_squared() < 1e-8)
+            {
+                Some((_, index)) => *index,
+                None if emitted[vertex_index].is_empty() => {
+                    new_vertices[vertex_index].tangent = tangent;
+                    emitted[vertex_index].push((tangent, vertex_index as u32));
+                    vertex_index as u32
+                }
+                None => {
+                    let index = new_vertices.len() as u32;
+                    new_vertices.push(TangentVertex {
+                        tangent,
+                        ..new_vertices[vertex_index]
+                    });
+                    emitted[vertex_index].push((tangent, index));
+                    index
+                }
+            };
+        }
+
+        new_faces.push(new_face);
+    }
+
+    (new_vertices, new_faces)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{na::Vector2, primitives};
+
+    fn assert_tangent(vertex: &TangentVertex, expected: Vector4<f32>) {
+        assert!(
+            (vertex.tangent - expected).norm() < 1e-5,
+            "{:?} isn't {expected:?}",
+            vertex.tangent
+        );
+    }
+
+    #[test]
+    fn quad_tangents_follow_u() {
+        let (vertices, faces) = primitives::plane(Vector2::new(2.0, 2.0), Vector2::new(2, 2));
+        let (new_vertices, new_faces) = generate_tangents(&vertices, &faces);
+
+        assert_eq!(new_vertices.len(), vertices.len());
+        assert_eq!(new_faces, faces);
+        for (vertex, new_vertex) in vertices.iter().zip(&new_vertices) {
+            assert_eq!(vertex.position, new_vertex.position);
+            assert_eq!(vertex.tex_coord, new_vertex.tex_coord);
+            assert_tangent(new_vertex, Vector4::new(1.0, 0.0, 0.0, 1.0));
+        }
+    }
+
+    #[test]
+    fn mirrored_uvs_flip_the_bitangent_sign() {
+        let (mut vertices, faces) = primitives::quad(Vector2::new(2.0, 2.0));
+        for vertex in &mut vertices {
+            vertex.tex_coord.x = 1.0 - vertex.tex_coord.x;
+        }
+        let (new_vertices, new_faces) = generate_tangents(&vertices, &faces);
+
+        assert_eq!(new_faces, faces);
+        for vertex in &new_vertices {
+            assert_tangent(vertex, Vector4::new(-1.0, 0.0, 0.0, -1.0));
+        }
+    }
+
+    #[test]
+    fn mirrored_seams_are_split() {
+        // Two quads sharing the edge at x = 0, the left one with mirrored UVs
+        let vertex = |x: f32, y: f32, u: f32| {
+            Vertex::new(Vector3::new(x, y, 0.0), Vector3::z(), Vector2::new(u, y))
+        };
+        let vertices = vec![
+            vertex(0.0, 0.0, 0.0),
+            vertex(0.0, 1.0, 0.0),
+            vertex(1.0, 0.0, 1.0),
+            vertex(1.0, 1.0, 1.0),
+            vertex(-1.0, 0.0, 1.0),
+            vertex(-1.0, 1.0, 1.0),
+        ];
+        let faces = vec![
+            Vector3::new(0, 2, 3),
+            Vector3::new(0, 3, 1),
+            Vector3::new(0, 1, 5),
+            Vector3::new(0, 5, 4),
+        ];
+        let (new_vertices, new_faces) = generate_tangents(&vertices, &faces);
+
+        // The seam vertices keep their index for the first face and are copied for the mirrored side
+        assert_eq!(new_vertices.len(), 8);
+        assert_eq!(&new_faces[..2], &faces[..2]);
+        for (face, new_face) in faces.iter().zip(&new_faces) {
+            let sign = if face.iter().any(|&index| index >= 4) {
+                -1.0
+            } else {
+                1.0
+            };
+            for (index, new_index) in face.iter().zip(new_face.iter()) {
+                let new_vertex = &new_vertices[*new_index as usize];
+                assert_eq!(new_vertex.position, vertices[*index as usize].position);
+                assert_tangent(new_vertex, Vector4::new(sign, 0.0, 0.0, sign));
+            }
+        }
+    }
+}
@@ -3,17 +3,20 @@ use palette::LinSrgba;
 use crate::{
     na::{Matrix4, Vector2, Vector3, Vector4},
     Alignment, Font, GUIObject, GameObject, Mesh, ProgramValue, ShaderProgram, Size, Transform,
-    UsageType, Vertex,
+    UsageType, Vertex, DEFAULT_LAYER,
 };
 
 pub struct TextObject<'a> {
     pub shader_program: &'a ShaderProgram,
     pub transform: Transform,
+    /// The world matrix of the parent, `transform` is relative to it
+    pub parent_matrix: Matrix4<f32>,
     text: String,
     font_size: u32,
     pub text_color: LinSrgba,
     pub size: Size,
     pub alignment: Alignment,
+    pub layer: u32,
     font: &'a Font,
     must_update: bool,
     internal_size: Vector2<f32>,
@@ -33,12 +36,14 @@ impl<'a> TextObject<'a> {
         Self {
             shader_program,
             transform: Default::default(),
+            parent_matrix: Matrix4::identity(),
             text: initial_text.clone(),
             font_size,
             font,
             text_color: LinSrgba::new(1.0, 1.0, 1.0, 1.0),
             size: Size::Auto,
             alignment: Alignment::TopLeft,
+            layer: DEFAULT_LAYER,
             must_update: false,
             internal_size: mesh_size,
             internal_mesh: Mesh::from_vertices(&vertices, &faces, usage_type),
@@ -60,6 +65,11 @@ impl<'a> TextObject<'a> {
         self
     }
 
+    pub fn with_layer(mut self, layer: u32) -> Self {
+        self.layer = layer;
+        self
+    }
+
     pub fn get_text(&self) -> &str {
         &self.text
     }
@@ -200,7 +210,11 @@ impl<'a> GameObject for TextObject<'a> {
     }
 
     fn get_transform_matrix(&self) -> Matrix4<f32> {
-        self.transform.to_matrix(false)
+        self.parent_matrix * self.transform.to_matrix()
+    }
+
+    fn get_layer(&self) -> u32 {
+        self.layer
     }
 }
 
@@ -215,7 +229,7 @@ impl<'a> GUIObject for TextObject<'a> {
             ),
             screen_size,
         );
-        transform.to_matrix(false)
+        self.parent_matrix * transform.to_matrix()
     }
 
     fn force_update(&mut self) {
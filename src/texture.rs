@@ -1,6 +1,7 @@
 use image::DynamicImage;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum WrapMode {
     Repeat = gl::REPEAT as isize,
     MirroredRepeat = gl::MIRRORED_REPEAT as isize,
@@ -9,6 +10,7 @@ pub enum WrapMode {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FilterMode {
     Nearest = gl::NEAREST as isize,
     Linear = gl::LINEAR as isize,
@@ -84,6 +86,59 @@ impl Texture {
 
         texture
     }
+
+    /// Sets the horizontal and vertical wrap modes separately
+    pub fn set_wrap_modes(&self, wrap_s: WrapMode, wrap_t: WrapMode) {
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.id);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, wrap_s as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, wrap_t as i32);
+        }
+    }
+
+    /// Creates a texture with uninitialized storage, used as a render target attachment
+    pub(crate) fn empty(
+        width: u32,
+        height: u32,
+        internal_format: u32,
+        format: u32,
+        data_type: u32,
+        filter_mode: FilterMode,
+    ) -> Self {
+        let mut texture = Self { id: 0 };
+
+        unsafe {
+            gl::GenTextures(1, &mut texture.id);
+            gl::BindTexture(gl::TEXTURE_2D, texture.id);
+
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_WRAP_S,
+                WrapMode::ClampToEdge as i32,
+            );
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_WRAP_T,
+                WrapMode::ClampToEdge as i32,
+            );
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, filter_mode as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, filter_mode as i32);
+
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                internal_format as i32,
+                width as i32,
+                height as i32,
+                0,
+                format,
+                data_type,
+                std::ptr::null(),
+            );
+        }
+
+        texture
+    }
 }
 
 impl Drop for Texture {
@@ -1,6 +1,15 @@
-use crate::na::{Matrix4, UnitQuaternion, Vector3};
+use crate::na::{Matrix4, Rotation3, Unit, UnitQuaternion, Vector3};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Space {
+    /// Relative to the transform's rotation
+    Local,
+    World,
+}
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Transform {
     pub position: Vector3<f32>,
     pub rotation: UnitQuaternion<f32>,
@@ -8,16 +17,95 @@ pub struct Transform {
 }
 
 impl Transform {
-    pub fn to_matrix(&self, is_camera: bool) -> Matrix4<f32> {
+    /// Decomposes a matrix made of a translation, rotation and scale. Skew is lost and a negative
+    /// determinant is represented by a negative x scale.
+    pub fn from_matrix(matrix: &Matrix4<f32>) -> Self {
+        let position = matrix.fixed_view::<3, 1>(0, 3).into_owned();
+        let mut basis = matrix.fixed_view::<3, 3>(0, 0).into_owned();
+
+        let mut scale = Vector3::new(
+            basis.column(0).norm(),
+            basis.column(1).norm(),
+            basis.column(2).norm(),
+        );
+        if basis.determinant() < 0.0 {
+            scale.x = -scale.x;
+        }
+
+        for i in 0..3 {
+            if scale[i] != 0.0 {
+                let column = basis.column(i) / scale[i];
+                basis.set_column(i, &column);
+            }
+        }
+
+        Self {
+            position,
+            rotation: UnitQuaternion::from_rotation_matrix(&Rotation3::from_matrix(&basis)),
+            scale,
+        }
+    }
+
+    /// Linearly interpolates position and scale and normalized-lerps the rotation. Cheaper than `slerp`
+    /// and close enough for the small steps of render interpolation.
+    pub fn lerp(&self, other: &Transform, t: f32) -> Self {
+        Self {
+            position: self.position.lerp(&other.position, t),
+            rotation: self.nlerp_rotation(&other.rotation, t),
+            scale: self.scale.lerp(&other.scale, t),
+        }
+    }
+
+    /// Takes the shortest path, `UnitQuaternion::nlerp` doesn't
+    fn nlerp_rotation(&self, other: &UnitQuaternion<f32>, t: f32) -> UnitQuaternion<f32> {
+        if self.rotation.coords.dot(&other.coords) < 0.0 {
+            self.rotation
+                .nlerp(&UnitQuaternion::new_unchecked(-other.into_inner()), t)
+        } else {
+            self.rotation.nlerp(other, t)
+        }
+    }
+
+    /// Linearly interpolates position and scale and spherically interpolates the rotation
+    pub fn slerp(&self, other: &Transform, t: f32) -> Self {
+        Self {
+            position: self.position.lerp(&other.position, t),
+            rotation: self
+                .rotation
+                .try_slerp(&other.rotation, t, f32::EPSILON)
+                .unwrap_or_else(|| self.nlerp_rotation(&other.rotation, t)),
+            scale: self.scale.lerp(&other.scale, t),
+        }
+    }
+
+    pub fn to_matrix(&self) -> Matrix4<f32> {
         let position = Matrix4::identity().prepend_translation(&self.position);
         let rotation = self.rotation.to_homogeneous();
         let scale = Matrix4::identity().prepend_nonuniform_scaling(&self.scale);
 
-        if is_camera {
-            (position * rotation).try_inverse().unwrap()
-        } else {
-            position * rotation * scale
-        }
+        position * rotation * scale
+    }
+
+    /// The inverse of the position and rotation, scale is ignored so it doesn't distort the view
+    pub fn to_view_matrix(&self) -> Matrix4<f32> {
+        let position = Matrix4::identity().prepend_translation(&self.position);
+        let rotation = self.rotation.to_homogeneous();
+
+        (position * rotation).try_inverse().unwrap()
+    }
+
+    pub fn get_euler_angles(&self) -> Vector3<f32> {
+        let (roll, pitch, yaw) = self.rotation.euler_angles();
+        Vector3::new(roll, pitch, yaw)
+    }
+
+    pub fn get_euler_angles_deg(&self) -> Vector3<f32> {
+        let euler_angles = self.get_euler_angles();
+        Vector3::new(
+            euler_angles.x.to_degrees(),
+            euler_angles.y.to_degrees(),
+            euler_angles.z.to_degrees(),
+        )
     }
 
     pub fn set_euler_angles(&mut self, euler_angles: &Vector3<f32>) {
@@ -32,6 +120,73 @@ impl Transform {
             euler_angles.z.to_radians(),
         ));
     }
+
+    /// -z, the direction cameras look in
+    pub fn forward(&self) -> Vector3<f32> {
+        self.rotation * -Vector3::z()
+    }
+
+    pub fn right(&self) -> Vector3<f32> {
+        self.rotation * Vector3::x()
+    }
+
+    pub fn up(&self) -> Vector3<f32> {
+        self.rotation * Vector3::y()
+    }
+
+    /// Rotates the transform so `forward` points at `target`. When `up` is parallel to the
+    /// direction, the current up (or back) vector is used instead so the result stays valid.
+    pub fn look_at(&mut self, target: &Vector3<f32>, up: &Vector3<f32>) {
+        let direction = target - self.position;
+        if direction.norm_squared() == 0.0 {
+            return;
+        }
+
+        let is_parallel = |up: &Vector3<f32>| {
+            direction.cross(up).norm_squared()
+                <= 1e-6 * direction.norm_squared() * up.norm_squared()
+        };
+        let up = [*up, self.up(), -self.forward(), Vector3::z()]
+            .into_iter()
+            .find(|up| !is_parallel(up))
+            .unwrap_or_else(Vector3::x);
+
+        self.rotation = UnitQuaternion::face_towards(&-direction, &up);
+    }
+
+    /// Rotates the transform around `point` by `angle` radians, changing both position and rotation
+    pub fn rotate_around(&mut self, point: &Vector3<f32>, axis: &Unit<Vector3<f32>>, angle: f32) {
+        let rotation = UnitQuaternion::from_axis_angle(axis, angle);
+        self.position = point + rotation * (self.position - point);
+        self.rotation = rotation * self.rotation;
+    }
+
+    pub fn translate(&mut self, delta: &Vector3<f32>, space: Space) {
+        self.position += match space {
+            Space::Local => self.rotation * delta,
+            Space::World => *delta,
+        };
+    }
+
+    /// Converts a point from local space to world space
+    pub fn transform_point(&self, point: &Vector3<f32>) -> Vector3<f32> {
+        self.position + self.rotation * point.component_mul(&self.scale)
+    }
+
+    /// Converts a point from world space to local space
+    pub fn inverse_transform_point(&self, point: &Vector3<f32>) -> Vector3<f32> {
+        (self.rotation.inverse() * (point - self.position)).component_div(&self.scale)
+    }
+
+    /// Converts a direction from local space to world space, ignoring position and scale
+    pub fn transform_direction(&self, direction: &Vector3<f32>) -> Vector3<f32> {
+        self.rotation * direction
+    }
+
+    /// Converts a direction from world space to local space, ignoring position and scale
+    pub fn inverse_transform_direction(&self, direction: &Vector3<f32>) -> Vector3<f32> {
+        self.rotation.inverse() * direction
+    }
 }
 
 impl Default for Transform {
@@ -43,3 +198,81 @@ impl Default for Transform {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: &Matrix4<f32>, b: &Matrix4<f32>) {
+        assert!((a - b).norm() < 1e-4, "{a} != {b}");
+    }
+
+    #[test]
+    fn from_matrix_round_trips() {
+        let transform = Transform {
+            position: Vector3::new(1.0, -2.0, 3.0),
+            rotation: UnitQuaternion::from_euler_angles(0.3, -1.2, 2.0),
+            scale: Vector3::new(2.0, 0.5, 4.0),
+        };
+        let decomposed = Transform::from_matrix(&transform.to_matrix());
+
+        assert!((decomposed.position - transform.position).norm() < 1e-5);
+        assert!((decomposed.scale - transform.scale).norm() < 1e-5);
+        assert!(decomposed.rotation.angle_to(&transform.rotation) < 1e-4);
+        assert_close(&decomposed.to_matrix(), &transform.to_matrix());
+    }
+
+    #[test]
+    fn from_matrix_keeps_mirroring_in_the_x_scale() {
+        let matrix = Matrix4::new_nonuniform_scaling(&Vector3::new(1.0, -1.0, 1.0))
+            * UnitQuaternion::from_euler_angles(0.0, 0.7, 0.0).to_homogeneous();
+        let decomposed = Transform::from_matrix(&matrix);
+
+        assert!(decomposed.scale.x < 0.0);
+        assert!(decomposed.scale.y > 0.0 && decomposed.scale.z > 0.0);
+        assert_close(&decomposed.to_matrix(), &matrix);
+    }
+
+    #[test]
+    fn from_matrix_handles_zero_scale() {
+        let matrix = Matrix4::new_nonuniform_scaling(&Vector3::new(0.0, 1.0, 1.0));
+        let decomposed = Transform::from_matrix(&matrix);
+
+        assert_eq!(decomposed.scale.x, 0.0);
+        assert!(decomposed
+            .rotation
+            .coords
+            .iter()
+            .all(|value| value.is_finite()));
+    }
+
+    #[test]
+    fn look_at_points_forward_at_the_target() {
+        let mut transform = Transform::default();
+        transform.look_at(&Vector3::new(3.0, 0.0, 0.0), &Vector3::y());
+
+        assert!((transform.forward() - Vector3::x()).norm() < 1e-5);
+        assert!((transform.up() - Vector3::y()).norm() < 1e-5);
+    }
+
+    #[test]
+    fn look_at_along_up_stays_valid() {
+        for target in [Vector3::new(0.0, 5.0, 0.0), Vector3::new(0.0, -5.0, 0.0)] {
+            let mut transform = Transform::default();
+            transform.look_at(&target, &Vector3::y());
+
+            assert!(transform
+                .rotation
+                .coords
+                .iter()
+                .all(|value| value.is_finite()));
+            assert!((transform.forward() - target.normalize()).norm() < 1e-5);
+        }
+
+        // Looking straight down again keeps working from a rotation that already faces down
+        let mut transform = Transform::default();
+        transform.look_at(&Vector3::new(0.0, -1.0, 0.0), &Vector3::y());
+        transform.look_at(&Vector3::new(0.0, -2.0, 0.0), &Vector3::y());
+        assert!((transform.forward() + Vector3::y()).norm() < 1e-5);
+    }
+}
@@ -0,0 +1,402 @@
+use crate::{
+    na::{Matrix4, Point3, UnitQuaternion, Vector3},
+    Transform,
+};
+
+/// A handle to a node in a `TransformHierarchy`. Handles of removed nodes are never reused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeId {
+    index: usize,
+    generation: u32,
+}
+
+struct Node {
+    local_transform: Transform,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    world_matrix: Matrix4<f32>,
+    dirty: bool,
+}
+
+struct Slot {
+    generation: u32,
+    node: Option<Node>,
+}
+
+/// Parent-child relationships between transforms. World matrices are cached and only recomputed
+/// for nodes whose own or ancestor's local transform changed.
+///
+/// Objects follow a node by setting their `parent_matrix` to `get_world_matrix` (or `get_parent_matrix`
+/// when their own `transform` is the node's local transform).
+#[derive(Default)]
+pub struct TransformHierarchy {
+    slots: Vec<Slot>,
+    free_slots: Vec<usize>,
+}
+
+impl TransformHierarchy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a root node
+    pub fn add(&mut self, local_transform: Transform) -> NodeId {
+        let node = Node {
+            local_transform,
+            parent: None,
+            children: Vec::new(),
+            world_matrix: Matrix4::identity(),
+            dirty: true,
+        };
+
+        if let Some(index) = self.free_slots.pop() {
+            let slot = &mut self.slots[index];
+            slot.node = Some(node);
+            NodeId {
+                index,
+                generation: slot.generation,
+            }
+        } else {
+            self.slots.push(Slot {
+                generation: 0,
+                node: Some(node),
+            });
+            NodeId {
+                index: self.slots.len() - 1,
+                generation: 0,
+            }
+        }
+    }
+
+    pub fn add_child(&mut self, parent: NodeId, local_transform: Transform) -> NodeId {
+        let id = self.add(local_transform);
+        self.set_parent(id, Some(parent));
+        id
+    }
+
+    /// Removes the node and all of its descendants
+    pub fn remove(&mut self, id: NodeId) {
+        self.detach(id);
+
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            let slot = &mut self.slots[id.index];
+            if let Some(node) = slot.node.take() {
+                stack.extend(node.children);
+                slot.generation += 1;
+                self.free_slots.push(id.index);
+            }
+        }
+    }
+
+    pub fn contains(&self, id: NodeId) -> bool {
+        self.slots
+            .get(id.index)
+            .is_some_and(|slot| slot.generation == id.generation && slot.node.is_some())
+    }
+
+    pub fn get_parent(&self, id: NodeId) -> Option<NodeId> {
+        self.node(id).parent
+    }
+
+    pub fn get_children(&self, id: NodeId) -> &[NodeId] {
+        &self.node(id).children
+    }
+
+    /// Keeps the local transform, so the node moves with its new parent.
+    /// Panics if `parent` is the node itself or one of its descendants.
+    pub fn set_parent(&mut self, id: NodeId, parent: Option<NodeId>) {
+        if let Some(parent) = parent {
+            assert!(
+                !self.is_ancestor_or_self(id, parent),
+                "A node can't be parented to itself or its descendants"
+            );
+        }
+
+        self.detach(id);
+        if let Some(parent) = parent {
+            self.node_mut(parent).children.push(id);
+        }
+        self.node_mut(id).parent = parent;
+        self.mark_dirty(id);
+    }
+
+    /// Keeps the world position, rotation and scale, so the node stays where it is
+    pub fn set_parent_keep_world(&mut self, id: NodeId, parent: Option<NodeId>) {
+        let position = self.get_world_position(id);
+        let rotation = self.get_world_rotation(id);
+        let scale = self.get_world_scale(id);
+
+        self.set_parent(id, parent);
+
+        self.set_world_rotation(id, rotation);
+        self.set_world_scale(id, scale);
+        self.set_world_position(id, position);
+    }
+
+    pub fn get_local_transform(&self, id: NodeId) -> Transform {
+        self.node(id).local_transform
+    }
+
+    pub fn set_local_transform(&mut self, id: NodeId, local_transform: Transform) {
+        self.node_mut(id).local_transform = local_transform;
+        self.mark_dirty(id);
+    }
+
+    pub fn get_local_position(&self, id: NodeId) -> Vector3<f32> {
+        self.node(id).local_transform.position
+    }
+
+    pub fn set_local_position(&mut self, id: NodeId, position: Vector3<f32>) {
+        self.node_mut(id).local_transform.position = position;
+        self.mark_dirty(id);
+    }
+
+    pub fn get_local_rotation(&self, id: NodeId) -> UnitQuaternion<f32> {
+        self.node(id).local_transform.rotation
+    }
+
+    pub fn set_local_rotation(&mut self, id: NodeId, rotation: UnitQuaternion<f32>) {
+        self.node_mut(id).local_transform.rotation = rotation;
+        self.mark_dirty(id);
+    }
+
+    pub fn get_local_scale(&self, id: NodeId) -> Vector3<f32> {
+        self.node(id).local_transform.scale
+    }
+
+    pub fn set_local_scale(&mut self, id: NodeId, scale: Vector3<f32>) {
+        self.node_mut(id).local_transform.scale = scale;
+        self.mark_dirty(id);
+    }
+
+    /// Recomputes the cached world matrix of this node and its ancestors if needed
+    pub fn get_world_matrix(&mut self, id: NodeId) -> Matrix4<f32> {
+        if !self.node(id).dirty {
+            return self.node(id).world_matrix;
+        }
+
+        let parent_matrix = self.get_parent_matrix(id);
+        let node = self.node_mut(id);
+        node.world_matrix = parent_matrix * node.local_transform.to_matrix();
+        node.dirty = false;
+
+        node.world_matrix
+    }
+
+    /// The world matrix of the node's parent, identity for root nodes
+    pub fn get_parent_matrix(&mut self, id: NodeId) -> Matrix4<f32> {
+        match self.node(id).parent {
+            Some(parent) => self.get_world_matrix(parent),
+            None => Matrix4::identity(),
+        }
+    }
+
+    /// Recomputes every dirty world matrix
+    pub fn update(&mut self) {
+        for index in 0..self.slots.len() {
+            if let Some(node) = &self.slots[index].node {
+                if node.dirty {
+                    self.get_world_matrix(NodeId {
+                        index,
+                        generation: self.slots[index].generation,
+                    });
+                }
+            }
+        }
+    }
+
+    pub fn get_world_position(&mut self, id: NodeId) -> Vector3<f32> {
+        self.get_world_matrix(id).column(3).xyz()
+    }
+
+    pub fn set_world_position(&mut self, id: NodeId, position: Vector3<f32>) {
+        let local_position = match self.get_parent_matrix(id).try_inverse() {
+            Some(inverse_parent) => {
+                inverse_parent
+                    .transform_point(&Point3::from(position))
+                    .coords
+            }
+            None => position,
+        };
+
+        self.set_local_position(id, local_position);
+    }
+
+    pub fn get_world_rotation(&self, id: NodeId) -> UnitQuaternion<f32> {
+        let node = self.node(id);
+        match node.parent {
+            Some(parent) => self.get_world_rotation(parent) * node.local_transform.rotation,
+            None => node.local_transform.rotation,
+        }
+    }
+
+    pub fn set_world_rotation(&mut self, id: NodeId, rotation: UnitQuaternion<f32>) {
+        let local_rotation = match self.node(id).parent {
+            Some(parent) => self.get_world_rotation(parent).inverse() * rotation,
+            None => rotation,
+        };
+
+        self.set_local_rotation(id, local_rotation);
+    }
+
+    /// The product of the scales up the hierarchy. Rotated parents with non-uniform scale skew
+    /// their children, which a scale vector can't represent.
+    pub fn get_world_scale(&self, id: NodeId) -> Vector3<f32> {
+        let node = self.node(id);
+        match node.parent {
+            Some(parent) => self
+                .get_world_scale(parent)
+                .component_mul(&node.local_transform.scale),
+            None => node.local_transform.scale,
+        }
+    }
+
+    pub fn set_world_scale(&mut self, id: NodeId, scale: Vector3<f32>) {
+        let local_scale = match self.node(id).parent {
+            Some(parent) => scale.component_div(&self.get_world_scale(parent)),
+            None => scale,
+        };
+
+        self.set_local_scale(id, local_scale);
+    }
+
+    fn node(&self, id: NodeId) -> &Node {
+        let slot = &self.slots[id.index];
+        assert_eq!(slot.generation, id.generation, "The node was removed");
+        slot.node.as_ref().expect("The node was removed")
+    }
+
+    fn node_mut(&mut self, id: NodeId) -> &mut Node {
+        let slot = &mut self.slots[id.index];
+        assert_eq!(slot.generation, id.generation, "The node was removed");
+        slot.node.as_mut().expect("The node was removed")
+    }
+
+    fn detach(&mut self, id: NodeId) {
+        if let Some(parent) = self.node_mut(id).parent.take() {
+            self.node_mut(parent).children.retain(|child| *child != id);
+        }
+    }
+
+    /// Whether `ancestor` is `id` or one of its ancestors, i.e. whether parenting `ancestor` to `id`
+    /// would make a cycle
+    pub fn is_ancestor_or_self(&self, ancestor: NodeId, mut id: NodeId) -> bool {
+        loop {
+            if id == ancestor {
+                return true;
+            }
+
+            match self.node(id).parent {
+                Some(parent) => id = parent,
+                None => return false,
+            }
+        }
+    }
+
+    fn mark_dirty(&mut self, id: NodeId) {
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            let node = self.node_mut(id);
+            // The descendants of a dirty node are already dirty
+            if !node.dirty {
+                node.dirty = true;
+                stack.extend_from_slice(&node.children);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn translation(x: f32, y: f32, z: f32) -> Transform {
+        Transform {
+            position: Vector3::new(x, y, z),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn children_follow_their_parent() {
+        let mut hierarchy = TransformHierarchy::new();
+        let parent = hierarchy.add(translation(1.0, 0.0, 0.0));
+        let child = hierarchy.add_child(parent, translation(0.0, 2.0, 0.0));
+
+        assert_eq!(
+            hierarchy.get_world_position(child),
+            Vector3::new(1.0, 2.0, 0.0)
+        );
+
+        hierarchy.set_local_position(parent, Vector3::new(5.0, 0.0, 0.0));
+        assert_eq!(
+            hierarchy.get_world_position(child),
+            Vector3::new(5.0, 2.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn set_parent_keep_world_keeps_the_world_transform() {
+        let mut hierarchy = TransformHierarchy::new();
+        let parent = hierarchy.add(Transform {
+            position: Vector3::new(3.0, 0.0, 0.0),
+            rotation: UnitQuaternion::from_euler_angles(0.0, 1.0, 0.0),
+            scale: Vector3::repeat(2.0),
+        });
+        let node = hierarchy.add(translation(1.0, 2.0, 3.0));
+
+        hierarchy.set_parent_keep_world(node, Some(parent));
+
+        assert_eq!(hierarchy.get_parent(node), Some(parent));
+        assert!((hierarchy.get_world_position(node) - Vector3::new(1.0, 2.0, 3.0)).norm() < 1e-5);
+        assert!(hierarchy.get_world_rotation(node).angle() < 1e-5);
+        assert!((hierarchy.get_world_scale(node) - Vector3::repeat(1.0)).norm() < 1e-5);
+    }
+
+    #[test]
+    fn removing_a_node_removes_its_descendants() {
+        let mut hierarchy = TransformHierarchy::new();
+        let root = hierarchy.add(Transform::default());
+        let child = hierarchy.add_child(root, Transform::default());
+        let grandchild = hierarchy.add_child(child, Transform::default());
+
+        hierarchy.remove(child);
+
+        assert!(hierarchy.contains(root));
+        assert!(!hierarchy.contains(child));
+        assert!(!hierarchy.contains(grandchild));
+        assert!(hierarchy.get_children(root).is_empty());
+
+        // The freed slot gets a new generation, so the old handle stays invalid
+        let new_node = hierarchy.add(Transform::default());
+        assert!(hierarchy.contains(new_node));
+        assert!(!hierarchy.contains(child) && !hierarchy.contains(grandchild));
+    }
+
+    #[test]
+    fn update_recomputes_dirty_descendants() {
+        let mut hierarchy = TransformHierarchy::new();
+        let root = hierarchy.add(Transform::default());
+        let child = hierarchy.add_child(root, translation(0.0, 1.0, 0.0));
+        hierarchy.update();
+
+        hierarchy.set_local_scale(root, Vector3::repeat(3.0));
+        hierarchy.update();
+
+        assert_eq!(
+            hierarchy.get_world_matrix(child).column(3).xyz(),
+            Vector3::new(0.0, 3.0, 0.0)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn parenting_to_a_descendant_panics() {
+        let mut hierarchy = TransformHierarchy::new();
+        let root = hierarchy.add(Transform::default());
+        let child = hierarchy.add_child(root, Transform::default());
+
+        hierarchy.set_parent(root, Some(child));
+    }
+}
@@ -14,14 +14,3 @@ pub fn tobj_from_slice_no_mtl(obj_slice: &[u8]) -> Result<Vec<Model>, LoadError>
     )?
     .0)
 }
-
-// With mtl from resources
-/*|path| {
-    tobj::load_mtl_buf(&mut if let Some(mtl) =
-        resources.get(path.file_name().unwrap().to_str().unwrap())
-    {
-        BufReader::new(mtl.as_slice())
-    } else {
-        BufReader::new(&[] as &[u8])
-    })
-}*/
@@ -1,7 +1,11 @@
-use crate::na::{Vector2, Vector3};
+use crate::{
+    na::{Matrix4, Vector2, Vector3, Vector4},
+    VertexAttribute, VertexLayout,
+};
 
 #[repr(C)]
 #[derive(Default, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vertex {
     pub position: Vector3<f32>,
     pub normal: Vector3<f32>,
@@ -29,3 +33,121 @@ impl Vertex {
         [3, 3, 2]
     }
 }
+
+unsafe impl VertexLayout for Vertex {
+    const ATTRIBUTES: &'static [VertexAttribute] = &[
+        VertexAttribute::f32("position", 3),
+        VertexAttribute::f32("normal", 3),
+        VertexAttribute::f32("tex_coord", 2),
+    ];
+}
+
+/// A vertex for normal mapped meshes. The bitangent is `cross(normal, tangent.xyz) * tangent.w`.
+#[repr(C)]
+#[derive(Default, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TangentVertex {
+    pub position: Vector3<f32>,
+    pub normal: Vector3<f32>,
+    pub tex_coord: Vector2<f32>,
+    pub tangent: Vector4<f32>,
+}
+
+impl TangentVertex {
+    pub fn new(
+        position: Vector3<f32>,
+        normal: Vector3<f32>,
+        tex_coord: Vector2<f32>,
+        tangent: Vector4<f32>,
+    ) -> Self {
+        Self {
+            position,
+            normal,
+            tex_coord,
+            tangent,
+        }
+    }
+}
+
+unsafe impl VertexLayout for TangentVertex {
+    const ATTRIBUTES: &'static [VertexAttribute] = &[
+        VertexAttribute::f32("position", 3),
+        VertexAttribute::f32("normal", 3),
+        VertexAttribute::f32("tex_coord", 2),
+        VertexAttribute::f32("tangent", 4),
+    ];
+}
+
+impl From<TangentVertex> for Vertex {
+    fn from(vertex: TangentVertex) -> Self {
+        Vertex::new(vertex.position, vertex.normal, vertex.tex_coord)
+    }
+}
+
+/// A vertex with a linear RGBA color, e.g. for PLY scans
+#[repr(C)]
+#[derive(Default, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ColorVertex {
+    pub position: Vector3<f32>,
+    pub normal: Vector3<f32>,
+    pub tex_coord: Vector2<f32>,
+    pub color: Vector4<f32>,
+}
+
+impl ColorVertex {
+    pub fn new(
+        position: Vector3<f32>,
+        normal: Vector3<f32>,
+        tex_coord: Vector2<f32>,
+        color: Vector4<f32>,
+    ) -> Self {
+        Self {
+            position,
+            normal,
+            tex_coord,
+            color,
+        }
+    }
+}
+
+unsafe impl VertexLayout for ColorVertex {
+    const ATTRIBUTES: &'static [VertexAttribute] = &[
+        VertexAttribute::f32("position", 3),
+        VertexAttribute::f32("normal", 3),
+        VertexAttribute::f32("tex_coord", 2),
+        VertexAttribute::f32("color", 4),
+    ];
+}
+
+impl From<ColorVertex> for Vertex {
+    fn from(vertex: ColorVertex) -> Self {
+        Vertex::new(vertex.position, vertex.normal, vertex.tex_coord)
+    }
+}
+
+/// Per-instance data for `Mesh::draw_instanced`
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Instance {
+    pub model: Matrix4<f32>,
+    pub color: Vector4<f32>,
+}
+
+impl Instance {
+    pub fn new(model: Matrix4<f32>, color: Vector4<f32>) -> Self {
+        Self { model, color }
+    }
+}
+
+unsafe impl VertexLayout for Instance {
+    /// A `mat4` attribute takes up one location per column
+    const ATTRIBUTES: &'static [VertexAttribute] = &[
+        VertexAttribute::f32("model_0", 4),
+        VertexAttribute::f32("model_1", 4),
+        VertexAttribute::f32("model_2", 4),
+        VertexAttribute::f32("model_3", 4),
+        VertexAttribute::f32("color", 4),
+    ];
+}
@@ -0,0 +1,194 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AttributeType {
+    F32 = gl::FLOAT as isize,
+    I8 = gl::BYTE as isize,
+    U8 = gl::UNSIGNED_BYTE as isize,
+    I16 = gl::SHORT as isize,
+    U16 = gl::UNSIGNED_SHORT as isize,
+    I32 = gl::INT as isize,
+    U32 = gl::UNSIGNED_INT as isize,
+}
+
+impl AttributeType {
+    pub fn size(&self) -> usize {
+        match self {
+            AttributeType::I8 | AttributeType::U8 => 1,
+            AttributeType::I16 | AttributeType::U16 => 2,
+            AttributeType::F32 | AttributeType::I32 | AttributeType::U32 => 4,
+        }
+    }
+
+    pub fn is_integer(&self) -> bool {
+        *self != AttributeType::F32
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VertexAttribute {
+    /// Only used for debugging and error messages, attributes are bound by their location
+    pub name: &'static str,
+    /// 1 to 4
+    pub components: u32,
+    pub attribute_type: AttributeType,
+    /// Maps integers to 0..1 (or -1..1 when signed) floats. Integer attributes that aren't
+    /// normalized reach the shader as integers (`ivec`/`uvec`), e.g. for bone indices.
+    pub normalized: bool,
+}
+
+impl VertexAttribute {
+    pub const fn new(
+        name: &'static str,
+        components: u32,
+        attribute_type: AttributeType,
+        normalized: bool,
+    ) -> Self {
+        Self {
+            name,
+            components,
+            attribute_type,
+            normalized,
+        }
+    }
+
+    pub const fn f32(name: &'static str, components: u32) -> Self {
+        Self::new(name, components, AttributeType::F32, false)
+    }
+
+    pub fn size(&self) -> usize {
+        self.components as usize * self.attribute_type.size()
+    }
+}
+
+/// Describes the attributes of a vertex type, in field order. Attribute `i` is bound to location `i`.
+///
+/// # Safety
+///
+/// Meshes copy vertices to and from the GPU as raw bytes and read positions straight out of them,
+/// so implementors must guarantee that:
+/// - the type is `#[repr(C)]` plain data without padding, references, pointers or `Drop` fields,
+///   and every bit pattern is a valid value;
+/// - there is one field per attribute, in order, each a scalar, an array or an nalgebra vector of
+///   the attribute's type and component count.
+///
+/// `assert_layout` catches attributes that don't add up to the size of the type, but can't check
+/// the field types.
+///
+/// ```ignore
+/// #[repr(C)]
+/// #[derive(Clone, Copy)]
+/// struct SkinnedVertex {
+///     position: Vector3<f32>,
+///     color: [u8; 4],
+///     bone_indices: [u16; 4],
+///     bone_weights: Vector4<f32>,
+/// }
+///
+/// unsafe impl VertexLayout for SkinnedVertex {
+///     const ATTRIBUTES: &'static [VertexAttribute] = &[
+///         VertexAttribute::f32("position", 3),
+///         VertexAttribute::new("color", 4, AttributeType::U8, true),
+///         VertexAttribute::new("bone_indices", 4, AttributeType::U16, false),
+///         VertexAttribute::f32("bone_weights", 4),
+///     ];
+/// }
+/// ```
+pub unsafe trait VertexLayout: Copy {
+    const ATTRIBUTES: &'static [VertexAttribute];
+
+    /// Panics if the attributes don't exactly cover the type, meshes call it before touching any
+    /// vertex memory
+    fn assert_layout() {
+        let offsets = Self::offsets();
+        let alignment = Self::ATTRIBUTES
+            .iter()
+            .map(|attribute| attribute.attribute_type.size())
+            .max()
+            .unwrap_or(1);
+        let size = Self::ATTRIBUTES
+            .last()
+            .zip(offsets.last())
+            .map_or(0, |(attribute, offset)| offset + attribute.size())
+            .div_ceil(alignment)
+            * alignment;
+
+        assert!(
+            size == std::mem::size_of::<Self>() && alignment <= std::mem::align_of::<Self>(),
+            "The attributes of `{}` take {size} bytes aligned to {alignment}, but the type is {} bytes aligned to {}",
+            std::any::type_name::<Self>(),
+            std::mem::size_of::<Self>(),
+            std::mem::align_of::<Self>(),
+        );
+    }
+
+    /// The byte offset of every attribute, following `repr(C)` alignment rules
+    fn offsets() -> Vec<usize> {
+        let mut offset = 0usize;
+
+        Self::ATTRIBUTES
+            .iter()
+            .map(|attribute| {
+                let alignment = attribute.attribute_type.size();
+                offset = offset.div_ceil(alignment) * alignment;
+                let attribute_offset = offset;
+                offset += attribute.size();
+                attribute_offset
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{na::Vector3, ColorVertex, Instance, TangentVertex, Vertex};
+
+    #[test]
+    fn built_in_layouts_match_their_types() {
+        Vertex::assert_layout();
+        TangentVertex::assert_layout();
+        ColorVertex::assert_layout();
+        Instance::assert_layout();
+
+        assert_eq!(Vertex::offsets(), vec![0, 12, 24]);
+    }
+
+    #[test]
+    fn offsets_follow_repr_c_alignment() {
+        #[repr(C)]
+        #[derive(Clone, Copy)]
+        struct Packed {
+            flag: u8,
+            value: u32,
+            short: [u16; 3],
+        }
+
+        unsafe impl VertexLayout for Packed {
+            const ATTRIBUTES: &'static [VertexAttribute] = &[
+                VertexAttribute::new("flag", 1, AttributeType::U8, false),
+                VertexAttribute::new("value", 1, AttributeType::U32, false),
+                VertexAttribute::new("short", 3, AttributeType::U16, false),
+            ];
+        }
+
+        Packed::assert_layout();
+        assert_eq!(Packed::offsets(), vec![0, 4, 8]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn missing_attributes_are_caught() {
+        #[repr(C)]
+        #[derive(Clone, Copy)]
+        struct Incomplete {
+            position: Vector3<f32>,
+            normal: Vector3<f32>,
+        }
+
+        unsafe impl VertexLayout for Incomplete {
+            const ATTRIBUTES: &'static [VertexAttribute] = &[VertexAttribute::f32("position", 3)];
+        }
+
+        Incomplete::assert_layout();
+    }
+}